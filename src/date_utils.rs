@@ -2,28 +2,31 @@ use jiff::civil::Date;
 
 /// Converts a jiff Date to a three-character IGC filename date format.
 ///
-/// Format: `{last_digit_of_year}{month}{day_character}`
+/// Format: `{last_digit_of_year}{month_character}{day_character}`
 ///
-/// where `day_character` is:
-/// - Digits 1-9 for days 1-9
-/// - Letters A-V for days 10-31 (A=10, B=11, ..., J=19, ..., V=31)
+/// where the month and day are written in base 36, as in short IGC
+/// filenames:
+/// - Digits 1-9 for months and days 1-9
+/// - Letters A-C for months 10-12 and A-V for days 10-31 (A=10, B=11, ...,
+///   J=19, ..., V=31)
 pub fn date_to_igc_filename_prefix(date: Date) -> String {
-    let last_digit_of_year = (date.year() % 10) as u8;
-    let month = date.month() as u8;
-    let day = date.day() as u8;
-
-    let day_character = match day {
-        1..=9 => char::from(b'0' + day),
-        10..=31 => char::from(b'A' + day - 10),
-        _ => unreachable!("Invalid day of month: {}", day),
+    let last_digit_of_year = date.year().rem_euclid(10);
+    let base_36 = |value: i8| match char::from_digit(value as u32, 36) {
+        Some(character) => character.to_ascii_uppercase(),
+        None => unreachable!("Invalid month or day: {}", value),
     };
 
-    format!("{last_digit_of_year}{month}{day_character}")
+    format!(
+        "{last_digit_of_year}{}{}",
+        base_36(date.month()),
+        base_36(date.day())
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::igc_filename::IgcFilename;
 
     #[test]
     fn test_date_to_igc_filename() {
@@ -35,7 +38,7 @@ mod tests {
         assert_eq!(date_to_igc_filename_prefix(date), "411");
 
         let date = Date::constant(2023, 12, 9);
-        assert_eq!(date_to_igc_filename_prefix(date), "3129");
+        assert_eq!(date_to_igc_filename_prefix(date), "3C9");
 
         // Test letter days (10-31)
         let date = Date::constant(2022, 7, 10);
@@ -50,4 +53,17 @@ mod tests {
         let date = Date::constant(2019, 5, 31);
         assert_eq!(date_to_igc_filename_prefix(date), "95V");
     }
+
+    #[test]
+    fn test_igc_filename_prefix_roundtrip() {
+        // Every day of a leap year is read back from a short filename
+        let latest = Date::constant(2024, 12, 31);
+        let mut date = Date::constant(2024, 1, 1);
+        while date <= latest {
+            let filename = format!("{}VABC1.igc", date_to_igc_filename_prefix(date));
+            let parsed = IgcFilename::parse_before(&filename, latest).unwrap();
+            assert_eq!(parsed.date, date, "{filename}");
+            date = date.tomorrow().unwrap();
+        }
+    }
 }
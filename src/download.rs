@@ -0,0 +1,483 @@
+use crate::error::Error;
+use crate::igc::{IgcHeader, IgcValidator};
+use crate::manifest::FileHasher;
+use crate::parser::{
+    DailyResult, IgcFile, parse_daily_result_table, parse_daily_results, parse_igc_files,
+    parse_task,
+};
+use crate::scheduler::{CacheValidators, Scheduler, TOO_MANY_REQUESTS_MARKER};
+use crate::task::Task;
+use crate::url_utils::{DailyUrlInfo, UrlInfo, extract_url_info, results_url};
+use indicatif::ProgressBar;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+/// Resolves any supported SoaringSpot URL into the list of daily results
+/// it refers to.
+///
+/// Competition and class URLs are expanded by loading the results overview
+/// page, daily URLs are returned as-is.
+pub async fn daily_urls_for_url(
+    scheduler: &Scheduler,
+    url: &Url,
+    include_practice: bool,
+) -> Result<Vec<DailyUrlInfo>, Error> {
+    let url_info = extract_url_info(url, scheduler.base_url())?;
+    Ok(match url_info {
+        UrlInfo::Daily(daily) => vec![daily],
+        UrlInfo::Class {
+            language,
+            competition,
+            class,
+        } => get_daily_urls_for_competition(scheduler, &language, &competition, include_practice)
+            .await?
+            .into_iter()
+            .filter(|info| info.class == class)
+            .collect(),
+        UrlInfo::Competition {
+            language,
+            competition,
+        } => {
+            get_daily_urls_for_competition(scheduler, &language, &competition, include_practice)
+                .await?
+        }
+    })
+}
+
+/// Loads the results overview page of a competition and returns the daily
+/// results of all classes.
+pub async fn get_daily_urls_for_competition(
+    scheduler: &Scheduler,
+    language: &str,
+    competition: &str,
+    include_practice: bool,
+) -> Result<Vec<DailyUrlInfo>, Error> {
+    let url = results_url(scheduler.base_url(), language, competition);
+
+    let html = scheduler.get_text(&url).await?;
+    Ok(parse_daily_results(&html, scheduler.base_url())?
+        .into_iter()
+        .filter(|info| {
+            // Filter out practice days if not requested
+            include_practice || !info.is_practice_day()
+        })
+        .collect())
+}
+
+/// Loads the daily results page and returns the IGC files listed on it.
+pub async fn get_igc_files(
+    scheduler: &Scheduler,
+    daily_info: &DailyUrlInfo,
+) -> Result<Vec<IgcFile>, Error> {
+    let url = daily_info.to_daily_url(scheduler.base_url());
+
+    let html = scheduler.get_text(&url).await?;
+    parse_igc_files(&html, scheduler.base_url())
+}
+
+/// Loads the daily results page and returns the full results table.
+pub async fn get_daily_results(
+    scheduler: &Scheduler,
+    daily_info: &DailyUrlInfo,
+) -> Result<Vec<DailyResult>, Error> {
+    let url = daily_info.to_daily_url(scheduler.base_url());
+
+    let html = scheduler.get_text(&url).await?;
+    parse_daily_result_table(&html, scheduler.base_url())
+}
+
+/// Like [get_daily_results], but sends a conditional request for the given
+/// `validators` and returns `None` if the page has not been modified since.
+///
+/// The returned validators can be used for the next request.
+pub async fn get_daily_results_if_modified(
+    scheduler: &Scheduler,
+    daily_info: &DailyUrlInfo,
+    validators: &CacheValidators,
+) -> Result<Option<(Vec<DailyResult>, CacheValidators)>, Error> {
+    let url = daily_info.to_daily_url(scheduler.base_url());
+
+    let Some(page) = scheduler.get_page(&url, validators).await? else {
+        return Ok(None);
+    };
+    Ok(Some((
+        parse_daily_result_table(&page.text, scheduler.base_url())?,
+        page.validators,
+    )))
+}
+
+/// Loads the task page and returns the task definition.
+pub async fn get_task(scheduler: &Scheduler, daily_info: &DailyUrlInfo) -> Result<Task, Error> {
+    let url = daily_info.to_task_url(scheduler.base_url());
+
+    let html = scheduler.get_text(&url).await?;
+    parse_task(&html)
+}
+
+/// Number of bytes at the start of a download that are checked for the
+/// "Too Many Requests" marker and parsed for the IGC header records.
+const HEAD_SIZE: usize = 64 * 1024;
+
+/// An IGC file that was downloaded by [download_igc_file].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadedIgcFile {
+    /// Metadata from the header records of the file
+    pub header: IgcHeader,
+    /// File size in bytes
+    pub size: u64,
+    /// Hex encoded SHA-256 hash of the file content
+    pub sha256: String,
+    /// Cache validators of the response, for [sync_igc_file]
+    pub validators: CacheValidators,
+}
+
+/// A previously downloaded version of an IGC file, see [sync_igc_file].
+#[derive(Debug, Clone, Copy)]
+pub struct LocalCopy<'a> {
+    /// Hex encoded SHA-256 hash of the local file
+    pub sha256: &'a str,
+    /// Cache validators of the response the local file was downloaded from
+    pub validators: &'a CacheValidators,
+}
+
+/// Outcome of [download_igc_file] and [sync_igc_file].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncOutcome {
+    /// The server reported that the file has not been modified.
+    NotModified,
+    /// The file was downloaded again, but its content has not changed.
+    Unchanged(DownloadedIgcFile),
+    /// The file is new or has changed and was written to the target path.
+    Updated(DownloadedIgcFile),
+}
+
+/// Downloads a single IGC file to `final_path`.
+///
+/// The response body is streamed into a temporary file in the same directory
+/// as `final_path` and then moved into place, so that `final_path` never
+/// contains a partial download and the rename does not cross filesystems.
+///
+/// The content is validated to look like an IGC flight log. Anything else,
+/// like HTML error pages, is moved to a `.invalid` file next to `final_path`
+/// instead and reported as [Error::InvalidIgc].
+///
+/// `progress_bar` is used to display the download progress in bytes.
+///
+/// Network errors, HTTP 5xx responses and "Too Many Requests" responses are
+/// retried according to the [RetryPolicy](crate::scheduler::RetryPolicy) of
+/// the `scheduler`.
+///
+/// Returns [SyncOutcome::Updated] with the header metadata, size and hash of
+/// the downloaded file.
+pub async fn download_igc_file(
+    scheduler: &Scheduler,
+    url: &str,
+    final_path: &Path,
+    progress_bar: &ProgressBar,
+) -> Result<SyncOutcome, Error> {
+    fetch_igc_file(scheduler, url, final_path, None, progress_bar).await
+}
+
+/// Updates the IGC file at `final_path` if it has changed on the server.
+///
+/// A conditional request is sent for the validators of `local`, and a file
+/// that is downloaded again only replaces the local copy if its SHA-256 hash
+/// differs. Like [download_igc_file], the replacement is atomic, invalid
+/// content never reaches `final_path` and failed requests are retried.
+pub async fn sync_igc_file(
+    scheduler: &Scheduler,
+    url: &str,
+    final_path: &Path,
+    local: LocalCopy<'_>,
+    progress_bar: &ProgressBar,
+) -> Result<SyncOutcome, Error> {
+    fetch_igc_file(scheduler, url, final_path, Some(local), progress_bar).await
+}
+
+async fn fetch_igc_file(
+    scheduler: &Scheduler,
+    url: &str,
+    final_path: &Path,
+    local: Option<LocalCopy<'_>>,
+    progress_bar: &ProgressBar,
+) -> Result<SyncOutcome, Error> {
+    let max_retries = scheduler.retry_policy().max_retries;
+    let mut retry_count = 0;
+
+    loop {
+        let (error, rate_limited) =
+            match try_fetch_igc_file(scheduler, url, final_path, local, progress_bar).await {
+                Ok(Some(outcome)) => return Ok(outcome),
+                Ok(None) => {
+                    let error = Error::RateLimited {
+                        url: url.to_string(),
+                        retries: max_retries,
+                    };
+                    (error, true)
+                }
+                Err(error) if error.is_transient() => (error, false),
+                Err(error) => return Err(error),
+            };
+
+        if retry_count >= max_retries {
+            return Err(error);
+        }
+
+        retry_count += 1;
+        scheduler.back_off(retry_count, rate_limited).await;
+    }
+}
+
+/// Downloads the IGC file once, returning `None` if the response was a
+/// "Too Many Requests" page.
+async fn try_fetch_igc_file(
+    scheduler: &Scheduler,
+    url: &str,
+    final_path: &Path,
+    local: Option<LocalCopy<'_>>,
+    progress_bar: &ProgressBar,
+) -> Result<Option<SyncOutcome>, Error> {
+    let default_validators = CacheValidators::default();
+    let validators = local.map_or(&default_validators, |local| local.validators);
+
+    let target_dir = final_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let mut response = scheduler.get_conditional(url, validators).await?;
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED && local.is_some() {
+        return Ok(Some(SyncOutcome::NotModified));
+    }
+    if !response.status().is_success() {
+        return Err(Error::HttpStatus {
+            status: response.status(),
+            url: url.to_string(),
+        });
+    }
+
+    let response_validators = CacheValidators::from_headers(response.headers());
+
+    progress_bar.set_position(0);
+    if let Some(length) = response.content_length() {
+        progress_bar.set_length(length);
+    }
+
+    // Create a temporary file next to the final location
+    let temp_file = tempfile::Builder::new()
+        .prefix(".")
+        .suffix(".igc.part")
+        .tempfile_in(target_dir)?;
+    let mut file = fs::File::from_std(temp_file.reopen()?);
+
+    // Stream the content into the temporary file, keeping the first
+    // bytes around to check for "Too Many Requests" and read the header
+    let mut head = Vec::new();
+    let mut validator = IgcValidator::new();
+    let mut hasher = FileHasher::new();
+    while let Some(chunk) = response.chunk().await? {
+        let remaining = HEAD_SIZE.saturating_sub(head.len());
+        head.extend_from_slice(&chunk[..remaining.min(chunk.len())]);
+        validator.update(&chunk);
+        hasher.update(&chunk);
+
+        file.write_all(&chunk).await?;
+        progress_bar.inc(chunk.len() as u64);
+    }
+    // Make sure the content is on disk before the file gets its final
+    // name, otherwise a crash could leave a truncated file there that
+    // later runs skip as existing
+    file.flush().await?;
+    file.sync_all().await?;
+    drop(file);
+
+    // The temp file is removed when it is dropped
+    if String::from_utf8_lossy(&head).contains(TOO_MANY_REQUESTS_MARKER) {
+        return Ok(None);
+    }
+
+    // Keep anything that is not an IGC file out of the final location
+    if let Err(source) = validator.finish() {
+        let path = quarantine_path(final_path);
+        temp_file.persist(&path).map_err(|error| error.error)?;
+        return Err(Error::InvalidIgc {
+            url: url.to_string(),
+            path,
+            source,
+        });
+    }
+
+    let (size, sha256) = hasher.finish();
+    let downloaded = DownloadedIgcFile {
+        header: IgcHeader::parse(&head),
+        size,
+        sha256,
+        validators: response_validators,
+    };
+
+    // Keep the local copy if nothing changed, the temp file is removed
+    // when it is dropped
+    if local.is_some_and(|local| local.sha256 == downloaded.sha256) {
+        return Ok(Some(SyncOutcome::Unchanged(downloaded)));
+    }
+
+    // Atomically move temp file to final location
+    temp_file.persist(final_path).map_err(|error| error.error)?;
+
+    Ok(Some(SyncOutcome::Updated(downloaded)))
+}
+
+/// Returns the path that rejected downloads for `final_path` are saved to.
+fn quarantine_path(final_path: &Path) -> PathBuf {
+    let mut path = final_path.as_os_str().to_owned();
+    path.push(".invalid");
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// Serves `body` once over plain HTTP and returns the URL to fetch it from.
+    async fn serve_once(body: &'static str) -> String {
+        serve_response_once("200 OK", body).await
+    }
+
+    /// Serves a response with the given status once and returns the URL to
+    /// fetch it from.
+    async fn serve_response_once(status: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = [0; 1024];
+            let _ = socket.read(&mut buffer).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 {}\r\netag: \"v2\"\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        format!("http://{addr}/file.igc")
+    }
+
+    #[tokio::test]
+    async fn test_download_igc_file_streams_into_target_dir() {
+        let body = "AXXX001\r\nHFDTE190625\r\nB1200004900000N01400000EA0050000500\r\n";
+        let url = serve_once(body).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let final_path = dir.path().join("56J_AM.igc");
+        let scheduler = Scheduler::new(reqwest::Client::new());
+        let progress_bar = ProgressBar::hidden();
+
+        let outcome = download_igc_file(&scheduler, &url, &final_path, &progress_bar)
+            .await
+            .unwrap();
+        let SyncOutcome::Updated(downloaded) = outcome else {
+            panic!("expected an update, got {outcome:?}");
+        };
+        assert_eq!(downloaded.header.manufacturer.as_deref(), Some("XXX"));
+        assert_eq!(downloaded.size, body.len() as u64);
+        assert_eq!(
+            downloaded.sha256,
+            crate::manifest::hash_file(&final_path).await.unwrap().1
+        );
+
+        assert_eq!(std::fs::read_to_string(&final_path).unwrap(), body);
+        assert_eq!(progress_bar.position(), body.len() as u64);
+
+        // No temporary files are left behind
+        let entries = std::fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_igc_file_rejects_html() {
+        let body = "<!DOCTYPE html>\n<html><body>Please log in</body></html>\n";
+        let url = serve_once(body).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let final_path = dir.path().join("56J_AM.igc");
+        let scheduler = Scheduler::new(reqwest::Client::new());
+
+        let error = download_igc_file(&scheduler, &url, &final_path, &ProgressBar::hidden())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::InvalidIgc { .. }));
+        assert!(!final_path.exists());
+
+        let quarantined = dir.path().join("56J_AM.igc.invalid");
+        assert_eq!(std::fs::read_to_string(quarantined).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn test_sync_igc_file_replaces_changed_file() {
+        let body = "AXXX001\r\nHFDTE190625\r\nB1200004900000N01400000EA0050000500\r\n";
+        let url = serve_once(body).await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let final_path = dir.path().join("56J_AM.igc");
+        std::fs::write(&final_path, "AXXX001\r\n").unwrap();
+
+        let scheduler = Scheduler::new(reqwest::Client::new());
+        let validators = CacheValidators::default();
+        let local = LocalCopy {
+            sha256: "outdated",
+            validators: &validators,
+        };
+
+        let outcome = sync_igc_file(&scheduler, &url, &final_path, local, &ProgressBar::hidden())
+            .await
+            .unwrap();
+
+        let SyncOutcome::Updated(downloaded) = outcome else {
+            panic!("expected an update, got {outcome:?}");
+        };
+        assert_eq!(downloaded.validators.etag.as_deref(), Some("\"v2\""));
+        assert_eq!(std::fs::read_to_string(&final_path).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn test_sync_igc_file_keeps_unchanged_file() {
+        let body = "AXXX001\r\nHFDTE190625\r\nB1200004900000N01400000EA0050000500\r\n";
+        let dir = tempfile::tempdir().unwrap();
+        let final_path = dir.path().join("56J_AM.igc");
+        std::fs::write(&final_path, body).unwrap();
+        let (_, sha256) = crate::manifest::hash_file(&final_path).await.unwrap();
+
+        let scheduler = Scheduler::new(reqwest::Client::new());
+        let validators = CacheValidators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+        let local = LocalCopy {
+            sha256: &sha256,
+            validators: &validators,
+        };
+
+        // Same content without support for conditional requests
+        let url = serve_once(body).await;
+        let outcome = sync_igc_file(&scheduler, &url, &final_path, local, &ProgressBar::hidden())
+            .await
+            .unwrap();
+        assert!(matches!(outcome, SyncOutcome::Unchanged(_)));
+
+        let url = serve_response_once("304 Not Modified", "").await;
+        let outcome = sync_igc_file(&scheduler, &url, &final_path, local, &ProgressBar::hidden())
+            .await
+            .unwrap();
+        assert_eq!(outcome, SyncOutcome::NotModified);
+
+        // The local copy is untouched and no temporary files are left behind
+        assert_eq!(std::fs::read_to_string(&final_path).unwrap(), body);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
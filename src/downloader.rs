@@ -0,0 +1,983 @@
+use crate::download::{
+    LocalCopy, SyncOutcome, daily_urls_for_url, download_igc_file, get_daily_results_if_modified,
+    get_task, sync_igc_file,
+};
+use crate::error::Error;
+use crate::export::{
+    ExportFormat, ResultRecord, read_competition_results, relative_path, write_results,
+};
+use crate::filter::ResultFilter;
+use crate::igc::HeaderMismatch;
+use crate::igc_filename::IgcFilename;
+use crate::layout::{Layout, UniquePaths};
+use crate::manifest::{MANIFEST_FILENAME, Manifest, ManifestEntry, hash_file};
+use crate::parser::IgcFile;
+use crate::plan::PlannedFile;
+use crate::scheduler::{CacheValidators, Scheduler};
+use crate::task_export::write_task_files;
+use crate::url_utils::DailyUrlInfo;
+use futures::{StreamExt, stream};
+use indicatif::ProgressBar;
+use jiff::Timestamp;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::fs;
+use url::Url;
+
+/// Default number of pages and IGC files that are downloaded in parallel.
+pub const DEFAULT_CONCURRENCY: NonZeroUsize = NonZeroUsize::new(4).unwrap();
+
+/// What a [Downloader] downloads and where it puts it.
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Directory the competition directories are created in
+    pub output_dir: PathBuf,
+    /// Include practice days in the downloads
+    pub include_practice: bool,
+    /// Maximum number of pages and IGC files to download in parallel
+    pub concurrency: NonZeroUsize,
+    /// Only download the flights of matching contestants
+    pub filter: ResultFilter,
+    /// Path of each IGC file below the output directory
+    pub layout: Layout,
+    /// Export the daily results next to the IGC files, plus a combined file
+    /// per competition
+    pub export: Option<ExportFormat>,
+    /// Write the task of each day as XCSoar and SeeYou files
+    pub tasks: bool,
+    /// Warn when the header of a downloaded IGC file does not match the
+    /// results
+    pub check_headers: bool,
+    /// Only load days and IGC files that changed since the last run, and
+    /// replace updated files
+    pub sync: bool,
+    /// Name the IGC files following the long IGC filename convention
+    pub long_filenames: bool,
+    /// Only list the files that would be downloaded in [Summary::plan],
+    /// without writing anything
+    pub dry_run: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from("."),
+            include_practice: false,
+            concurrency: DEFAULT_CONCURRENCY,
+            filter: ResultFilter::default(),
+            layout: Layout::default(),
+            export: None,
+            tasks: false,
+            check_headers: false,
+            sync: false,
+            long_filenames: false,
+            dry_run: false,
+        }
+    }
+}
+
+/// Downloads the IGC files of a competition, class or day into the output
+/// directory, and keeps a manifest of them per competition.
+///
+/// Nothing is printed while downloading. Progress is reported as [Event]s to
+/// the callback of [Downloader::with_events].
+pub struct Downloader<'a> {
+    scheduler: &'a Scheduler,
+    options: DownloadOptions,
+    events: Box<dyn Fn(Event<'_>) + Send + Sync + 'a>,
+}
+
+/// Counts of what was done in a single run.
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// Days that were loaded for the first time
+    pub new_days: usize,
+    /// Days that were loaded again
+    pub changed_days: usize,
+    /// Days that the server reported as unchanged
+    pub unchanged_days: usize,
+    pub failed_days: Vec<(DailyUrlInfo, Error)>,
+    pub downloaded_files: usize,
+    pub updated_files: usize,
+    pub failed_files: usize,
+    /// Files that would be downloaded, only listed in a dry run
+    pub plan: Vec<PlannedFile>,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} new days, {} changed days, {} unchanged days, {} failed days, \
+             {} new flights, {} updated flights, {} failed flights",
+            self.new_days,
+            self.changed_days,
+            self.unchanged_days,
+            self.failed_days.len(),
+            self.downloaded_files,
+            self.updated_files,
+            self.failed_files
+        )
+    }
+}
+
+/// Progress of a [Downloader::run], in the order it happens.
+#[derive(Debug)]
+pub enum Event<'a> {
+    /// The manifest of a previous run could not be read, so a new one is
+    /// started
+    InvalidManifest {
+        path: &'a Path,
+        error: &'a Error,
+    },
+    /// This many daily results pages are going to be loaded
+    LoadingDays(usize),
+    /// A daily results page is requested
+    LoadingDay(&'a DailyUrlInfo),
+    /// A daily results page was loaded, or was unchanged since the last sync
+    DayLoaded {
+        day: &'a DailyUrlInfo,
+        changed: bool,
+    },
+    DayFailed {
+        day: &'a DailyUrlInfo,
+        error: &'a Error,
+    },
+    /// All daily results pages were loaded, with this many IGC files to
+    /// download
+    FilesFound(usize),
+    TaskExported(&'a Path),
+    TaskFailed {
+        day: &'a DailyUrlInfo,
+        error: &'a Error,
+    },
+    /// The path of a flight is already used by another flight, so it is
+    /// saved to `unique_path` instead
+    PathTaken {
+        day: &'a DailyUrlInfo,
+        callsign: &'a str,
+        path: &'a Path,
+        unique_path: &'a Path,
+    },
+    /// A directory for IGC files was created
+    CreatedDirectory(&'a Path),
+    /// An IGC file is being downloaded. `bytes` is hidden and counts the
+    /// downloaded bytes, it can be added to a `MultiProgress` to show them.
+    Downloading {
+        filename: &'a str,
+        bytes: &'a ProgressBar,
+    },
+    /// The download started with [Event::Downloading] ended, successfully or
+    /// not
+    DownloadEnded {
+        filename: &'a str,
+        bytes: &'a ProgressBar,
+    },
+    FileDone {
+        filename: &'a str,
+        status: FileStatus,
+    },
+    FileFailed {
+        filename: &'a str,
+        error: &'a Error,
+    },
+    /// An existing file could not be read to add it to the manifest
+    ReadFailed {
+        filename: &'a str,
+        error: &'a Error,
+    },
+    /// The header of a downloaded file does not match the results, only
+    /// checked with [DownloadOptions::check_headers]
+    HeaderMismatch {
+        filename: &'a str,
+        mismatch: &'a HeaderMismatch,
+    },
+    /// A downloaded file keeps the name from the layout despite
+    /// [DownloadOptions::long_filenames], because there is no logger in its
+    /// A record
+    MissingLogger {
+        filename: &'a str,
+    },
+    ManifestWritten(&'a Path),
+    /// A results file of an earlier run could not be read, so it is left out
+    /// of the combined results file
+    InvalidResults {
+        path: &'a Path,
+        error: &'a Error,
+    },
+    Exported(&'a Path),
+}
+
+impl<'a> Downloader<'a> {
+    pub fn new(scheduler: &'a Scheduler, options: DownloadOptions) -> Self {
+        Self {
+            scheduler,
+            options,
+            events: Box::new(|_| {}),
+        }
+    }
+
+    /// Calls `events` with the progress of each run, e.g. to show it to the
+    /// user. Events are ignored by default.
+    pub fn with_events(mut self, events: impl Fn(Event<'_>) + Send + Sync + 'a) -> Self {
+        self.events = Box::new(events);
+        self
+    }
+
+    /// Downloads everything below `url` once.
+    pub async fn run(&self, url: &Url) -> Result<Summary, Error> {
+        let Self {
+            scheduler,
+            options,
+            events,
+        } = self;
+        let mut daily_urls = daily_urls_for_url(scheduler, url, options.include_practice).await?;
+
+        let concurrency = options.concurrency.get();
+        let output_dir = &options.output_dir;
+        let sync = options.sync;
+
+        // Load the manifests of previous runs, so that their entries are kept
+        let mut manifests = BTreeMap::new();
+        for daily_url in &daily_urls {
+            let competition = &daily_url.competition;
+            if !manifests.contains_key(competition) {
+                let manifest = load_manifest(output_dir, competition, events).await;
+                manifests.insert(competition.clone(), manifest);
+            }
+        }
+
+        // Days are synced in the language they were loaded in, so that their
+        // cache validators and download URLs still apply
+        if sync {
+            for daily_url in &mut daily_urls {
+                let day = manifests[&daily_url.competition].day(daily_url);
+                if let Some(language) = day.and_then(|day| day.language.clone()) {
+                    daily_url.language = language;
+                }
+            }
+        }
+
+        // Load the daily results pages concurrently, but keep them in order
+        events(Event::LoadingDays(daily_urls.len()));
+        let daily_results = stream::iter(daily_urls)
+            .map(|daily_url| {
+                // Only days that changed since the last run are loaded when syncing
+                let validators = match manifests[&daily_url.competition].day(&daily_url) {
+                    Some(day) if sync => day.validators.clone(),
+                    _ => CacheValidators::default(),
+                };
+
+                async move {
+                    events(Event::LoadingDay(&daily_url));
+
+                    let result =
+                        get_daily_results_if_modified(scheduler, &daily_url, &validators).await;
+                    events(match &result {
+                        Ok(results) => Event::DayLoaded {
+                            day: &daily_url,
+                            changed: results.is_some(),
+                        },
+                        Err(error) => Event::DayFailed {
+                            day: &daily_url,
+                            error,
+                        },
+                    });
+
+                    (daily_url, result)
+                }
+            })
+            .buffered(concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        // Keep going with the days that could be loaded and report the others
+        // at the end, instead of discarding everything discovered so far
+        let mut summary = Summary::default();
+        let filter = &options.filter;
+        let daily_results = daily_results
+            .into_iter()
+            .filter_map(|(daily_url, result)| match result {
+                Ok(Some((results, validators))) => {
+                    match manifests[&daily_url.competition].day(&daily_url) {
+                        Some(_) => summary.changed_days += 1,
+                        None => summary.new_days += 1,
+                    }
+                    Some((daily_url, results, validators))
+                }
+                Ok(None) => {
+                    summary.unchanged_days += 1;
+                    None
+                }
+                Err(e) => {
+                    summary.failed_days.push((daily_url, e));
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // The filter only selects the flights to download, the exports
+        // always contain the whole results
+        let total_files = daily_results
+            .iter()
+            .flat_map(|(_, results, _)| results)
+            .filter(|result| result.download_url.is_some() && filter.matches(result))
+            .count();
+
+        // Nothing is written in a dry run, including tasks and exports
+        let dry_run = options.dry_run;
+        let tasks = options.tasks && !dry_run;
+        let export = options.export.filter(|_| !dry_run);
+
+        events(Event::FilesFound(total_files));
+        if total_files == 0 && export.is_none() && !tasks && !dry_run {
+            return Ok(summary);
+        }
+
+        let mut downloads = vec![];
+        let mut created_dirs = BTreeSet::new();
+        let mut unique_paths = UniquePaths::default();
+        let mut exports = vec![];
+        let mut loaded_days = vec![];
+        for (daily_info, results, validators) in daily_results {
+            // The task and results files always go into
+            // {output}/{competition}/{class}/{date}/, the IGC files follow the layout
+            let date_str = daily_info.date.strftime("%Y-%m-%d").to_string();
+            let day_dir = Path::new(&daily_info.competition)
+                .join(&daily_info.class)
+                .join(&date_str);
+            let target_dir = output_dir.join(&day_dir);
+
+            if tasks || export.is_some() {
+                fs::create_dir_all(&target_dir).await?;
+            }
+
+            if tasks {
+                let name = format!("{} {}", daily_info.task_name, daily_info.date);
+                let result = match get_task(scheduler, &daily_info).await {
+                    Ok(task) => write_task_files(&target_dir, &name, &task).await,
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(paths) => {
+                        for path in paths {
+                            events(Event::TaskExported(&path));
+                        }
+                    }
+                    Err(error) => events(Event::TaskFailed {
+                        day: &daily_info,
+                        error: &error,
+                    }),
+                }
+            }
+
+            // Flights from previous runs keep the file they were saved as,
+            // e.g. with a number or a long filename, unless the layout now
+            // puts them into another directory. The others claim their paths
+            // in the order of the callsigns, so that flights that would share
+            // a file are always told apart the same way, with or without a
+            // filter.
+            let manifest = &manifests[&daily_info.competition];
+            let is_taken = |path: &Path, callsign: &str| {
+                let relative_path = manifest_path(&daily_info, path);
+                manifest
+                    .get(&relative_path)
+                    .is_some_and(|entry| !entry.is_flight(&daily_info, callsign))
+            };
+            let mut paths = vec![None; results.len()];
+            let mut order = results
+                .iter()
+                .enumerate()
+                .filter(|(_, result)| result.download_url.is_some())
+                .collect::<Vec<_>>();
+            order.sort_by_key(|(_, result)| (&result.callsign, &result.download_url));
+            let mut new_flights = vec![];
+            for (index, result) in order {
+                let path = options.layout.render(&daily_info, result);
+                let stored_path = manifest
+                    .flight(&daily_info, &result.callsign)
+                    .map(|entry| output_path(&daily_info, &entry.path))
+                    .filter(|stored_path| stored_path.parent() == path.parent());
+                match stored_path {
+                    Some(stored_path) => {
+                        let unique_path = unique_paths
+                            .claim(stored_path, |path| is_taken(path, &result.callsign));
+                        paths[index] = Some(unique_path);
+                    }
+                    None => new_flights.push((index, result, path)),
+                }
+            }
+            for (index, result, path) in new_flights {
+                let unique_path =
+                    unique_paths.claim(path.clone(), |path| is_taken(path, &result.callsign));
+                if unique_path != path && filter.matches(result) {
+                    events(Event::PathTaken {
+                        day: &daily_info,
+                        callsign: &result.callsign,
+                        path: &path,
+                        unique_path: &unique_path,
+                    });
+                }
+                paths[index] = Some(unique_path);
+            }
+
+            let mut records = vec![];
+            for (result, path) in results.into_iter().zip(paths) {
+                let path = path.unwrap_or_else(|| options.layout.render(&daily_info, &result));
+                let file_path = output_dir.join(&path);
+                let filename = result.download_url.as_ref().map(|_| {
+                    let filename = file_path.file_name().unwrap_or_default();
+                    filename.to_string_lossy().into_owned()
+                });
+                let matches = filter.matches(&result);
+
+                // Flights that are filtered out are only linked if they were
+                // downloaded before
+                if export.is_some() {
+                    let igc_file = filename
+                        .as_ref()
+                        .filter(|_| matches || file_path.exists())
+                        .map(|_| relative_path(&day_dir, &path));
+                    records.push(ResultRecord::new(&daily_info, &result, igc_file));
+                }
+
+                if !matches {
+                    continue;
+                }
+                if let (Some(filename), Some(download_url)) = (filename, result.download_url) {
+                    if dry_run {
+                        summary.plan.push(PlannedFile::new(
+                            &daily_info,
+                            &result.callsign,
+                            &result.pilot,
+                            &download_url,
+                            &file_path,
+                        ));
+                        continue;
+                    }
+
+                    let target_dir = file_path.parent().unwrap_or(output_dir);
+                    if created_dirs.insert(target_dir.to_path_buf()) {
+                        fs::create_dir_all(target_dir).await?;
+                        events(Event::CreatedDirectory(target_dir));
+                    }
+
+                    let relative_path = manifest_path(&daily_info, &path);
+                    downloads.push(Download {
+                        daily_info: daily_info.clone(),
+                        relative_path,
+                        file_path,
+                        filename,
+                        igc_file: IgcFile {
+                            callsign: result.callsign,
+                            download_url,
+                        },
+                    });
+                }
+            }
+
+            if !records.is_empty() {
+                exports.push((daily_info.clone(), day_dir, records));
+            }
+
+            // Days are only complete without a filter, otherwise they are loaded
+            // again on the next sync, in case the filter changes
+            if filter.is_empty() {
+                loaded_days.push((daily_info, validators));
+            }
+        }
+
+        if dry_run {
+            return Ok(summary);
+        }
+
+        // Download the IGC files concurrently, sharing the request budget
+        let downloader = FileDownloader {
+            scheduler,
+            events,
+            check_headers: options.check_headers,
+            sync,
+            long_filenames: options.long_filenames,
+            renames: Mutex::new(()),
+        };
+        let download_results = stream::iter(downloads)
+            .map(|download| {
+                let previous =
+                    manifests[&download.daily_info.competition].get(&download.relative_path);
+                let downloader = &downloader;
+                async move {
+                    let daily_info = download.daily_info.clone();
+                    let result = downloader.download(download, previous).await;
+                    (daily_info, result)
+                }
+            })
+            .buffer_unordered(concurrency)
+            .collect::<Vec<_>>()
+            .await;
+
+        // Days with failed downloads are not recorded, so that they are loaded
+        // again on the next sync
+        for (daily_info, result) in download_results {
+            let entry = match result {
+                Ok((status, entry)) => {
+                    match status {
+                        FileStatus::Downloaded => summary.downloaded_files += 1,
+                        FileStatus::Updated => summary.updated_files += 1,
+                        FileStatus::Skipped | FileStatus::Unchanged => {}
+                    }
+                    entry
+                }
+                Err(_) => {
+                    summary.failed_files += 1;
+                    loaded_days.retain(|(day, _)| *day != daily_info);
+                    None
+                }
+            };
+            if let Some(entry) = entry {
+                manifests
+                    .get_mut(&daily_info.competition)
+                    .unwrap()
+                    .insert(entry);
+            }
+        }
+        for (daily_info, validators) in loaded_days {
+            manifests
+                .get_mut(&daily_info.competition)
+                .unwrap()
+                .insert_day(&daily_info, validators);
+        }
+
+        // The names of the IGC files in the results are only known now
+        if options.long_filenames {
+            for (daily_info, day_dir, records) in &mut exports {
+                let manifest = &manifests[&daily_info.competition];
+                for record in records
+                    .iter_mut()
+                    .filter(|record| record.igc_file.is_some())
+                {
+                    if let Some(entry) = manifest.flight(daily_info, &record.callsign) {
+                        let path = output_path(daily_info, &entry.path);
+                        record.igc_file = Some(relative_path(day_dir, &path));
+                    }
+                }
+            }
+        }
+
+        write_manifests(output_dir, manifests, events).await?;
+
+        if let Some(format) = export {
+            write_exports(output_dir, exports, format, events).await?;
+        }
+
+        Ok(summary)
+    }
+}
+
+/// A single IGC file to download.
+struct Download {
+    daily_info: DailyUrlInfo,
+    filename: String,
+    /// Path relative to the competition directory, as stored in the manifest
+    relative_path: PathBuf,
+    file_path: PathBuf,
+    igc_file: IgcFile,
+}
+
+/// What was done with an IGC file, see [Event::FileDone].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    /// The file already existed and was not checked for changes
+    Skipped,
+    /// The file was checked, but has not changed since the last run
+    Unchanged,
+    Downloaded,
+    /// The file has changed and was replaced
+    Updated,
+}
+
+/// Downloads the IGC files of a single run.
+struct FileDownloader<'a> {
+    scheduler: &'a Scheduler,
+    events: &'a (dyn Fn(Event<'_>) + Send + Sync + 'a),
+    check_headers: bool,
+    sync: bool,
+    long_filenames: bool,
+    /// Held while picking a free long filename, so that concurrent
+    /// downloads from the same logger don't pick the same one
+    renames: Mutex<()>,
+}
+
+impl FileDownloader<'_> {
+    /// Downloads a single IGC file, or updates it if it already exists and
+    /// syncing is enabled.
+    ///
+    /// Returns what was done, and the new manifest entry for the file if
+    /// anything changed.
+    async fn download(
+        &self,
+        download: Download,
+        previous: Option<&ManifestEntry>,
+    ) -> Result<(FileStatus, Option<ManifestEntry>), Error> {
+        let Download {
+            daily_info,
+            filename,
+            relative_path,
+            file_path,
+            igc_file,
+        } = download;
+        let events = self.events;
+
+        // Existing files are only requested again when syncing, and only if
+        // the server can tell whether they changed or the flight now links
+        // to another file. Without validators the whole file would have to
+        // be downloaded on every sync just to compare its hash.
+        let exists = file_path.exists();
+        let moved = previous.is_some_and(|previous| previous.source_url != igc_file.download_url);
+        let check = previous.is_some_and(|previous| moved || !previous.validators.is_empty());
+        if exists && !(self.sync && check) {
+            let status = FileStatus::Skipped;
+            events(Event::FileDone {
+                filename: &filename,
+                status,
+            });
+            if previous.is_some() {
+                return Ok((status, None));
+            }
+
+            // Files from before the manifest was introduced are added from disk
+            let result =
+                existing_file_entry(&daily_info, &igc_file, relative_path, &file_path).await;
+            if let Err(error) = &result {
+                events(Event::ReadFailed {
+                    filename: &filename,
+                    error,
+                });
+            }
+            return result.map(|entry| (status, Some(entry)));
+        }
+
+        // The hash of the local copy is needed to detect changes, and the
+        // validators only apply to the URL they were received from
+        let no_validators = CacheValidators::default();
+        let local = previous.filter(|_| exists).map(|previous| LocalCopy {
+            sha256: &previous.sha256,
+            validators: match moved {
+                true => &no_validators,
+                false => &previous.validators,
+            },
+        });
+
+        let bytes = ProgressBar::hidden();
+        events(Event::Downloading {
+            filename: &filename,
+            bytes: &bytes,
+        });
+
+        let url = &igc_file.download_url;
+        let result = match local {
+            Some(local) => sync_igc_file(self.scheduler, url, &file_path, local, &bytes).await,
+            None => download_igc_file(self.scheduler, url, &file_path, &bytes).await,
+        };
+
+        events(Event::DownloadEnded {
+            filename: &filename,
+            bytes: &bytes,
+        });
+
+        let status = if exists {
+            FileStatus::Updated
+        } else {
+            FileStatus::Downloaded
+        };
+        let downloaded = match result {
+            Ok(SyncOutcome::NotModified) => {
+                let status = FileStatus::Unchanged;
+                events(Event::FileDone {
+                    filename: &filename,
+                    status,
+                });
+                return Ok((status, None));
+            }
+            Ok(SyncOutcome::Unchanged(downloaded)) => {
+                let status = FileStatus::Unchanged;
+                events(Event::FileDone {
+                    filename: &filename,
+                    status,
+                });
+
+                // Remember the new URL and validators for the next sync
+                let mut entry = match previous {
+                    Some(previous) => previous.clone(),
+                    None => {
+                        existing_file_entry(&daily_info, &igc_file, relative_path, &file_path)
+                            .await?
+                    }
+                };
+                entry.source_url = igc_file.download_url.clone();
+                entry.validators = downloaded.validators;
+                return Ok((status, Some(entry)));
+            }
+            Ok(SyncOutcome::Updated(downloaded)) => {
+                events(Event::FileDone {
+                    filename: &filename,
+                    status,
+                });
+                downloaded
+            }
+            Err(error) => {
+                events(Event::FileFailed {
+                    filename: &filename,
+                    error: &error,
+                });
+                return Err(error);
+            }
+        };
+
+        if self.check_headers {
+            for mismatch in downloaded.header.check(&igc_file.callsign, daily_info.date) {
+                events(Event::HeaderMismatch {
+                    filename: &filename,
+                    mismatch: &mismatch,
+                });
+            }
+        }
+
+        // Updated files keep the name they got when they were downloaded
+        let relative_path = if self.long_filenames && status == FileStatus::Downloaded {
+            match IgcFilename::from_header(&downloaded.header, daily_info.date) {
+                Some(long_filename) => {
+                    let path = {
+                        let _guard = self.renames.lock().unwrap();
+                        rename_to_long_filename(&file_path, long_filename)?
+                    };
+                    relative_path.with_file_name(path.file_name().unwrap_or_default())
+                }
+                None => {
+                    events(Event::MissingLogger {
+                        filename: &filename,
+                    });
+                    relative_path
+                }
+            }
+        } else {
+            relative_path
+        };
+
+        let mut entry = ManifestEntry::new(
+            &daily_info,
+            &igc_file.callsign,
+            &igc_file.download_url,
+            relative_path,
+            downloaded.size,
+            downloaded.sha256,
+            Timestamp::now(),
+        );
+        entry.validators = downloaded.validators;
+        Ok((status, Some(entry)))
+    }
+}
+
+/// Renames a downloaded file to its long IGC filename and returns the new
+/// path.
+///
+/// If another flight of the same logger on the same day already has the
+/// name, the next flight number is used.
+fn rename_to_long_filename(
+    file_path: &Path,
+    mut long_filename: IgcFilename,
+) -> Result<PathBuf, Error> {
+    let mut path = file_path.with_file_name(long_filename.to_long());
+    while path != file_path && path.exists() {
+        long_filename.flight_number += 1;
+        path = file_path.with_file_name(long_filename.to_long());
+    }
+
+    std::fs::rename(file_path, &path)?;
+    Ok(path)
+}
+
+/// Returns the path of an IGC file in the manifest from its path relative to
+/// the output directory.
+///
+/// The manifest is stored in the competition directory, so paths are
+/// relative to it even if the layout puts files elsewhere.
+fn manifest_path(daily_info: &DailyUrlInfo, path: &Path) -> PathBuf {
+    match path.strip_prefix(&daily_info.competition) {
+        Ok(relative_path) => relative_path.to_path_buf(),
+        Err(_) => Path::new("..").join(path),
+    }
+}
+
+/// Returns the path of an IGC file relative to the output directory from its
+/// path in the manifest, the inverse of [manifest_path].
+fn output_path(daily_info: &DailyUrlInfo, relative_path: &Path) -> PathBuf {
+    match relative_path.strip_prefix("..") {
+        Ok(path) => path.to_path_buf(),
+        Err(_) => Path::new(&daily_info.competition).join(relative_path),
+    }
+}
+
+/// Loads the manifest of a previous run, or starts a new one.
+async fn load_manifest(
+    output_dir: &Path,
+    competition: &str,
+    events: impl Fn(Event<'_>),
+) -> Manifest {
+    let path = output_dir.join(competition).join(MANIFEST_FILENAME);
+    let manifest = match Manifest::load(&path).await {
+        Ok(manifest) => manifest,
+        Err(error) => {
+            events(Event::InvalidManifest {
+                path: &path,
+                error: &error,
+            });
+            None
+        }
+    };
+    manifest.unwrap_or_else(|| Manifest::new(competition))
+}
+
+/// Creates the manifest entry for an IGC file from a previous run.
+async fn existing_file_entry(
+    daily_info: &DailyUrlInfo,
+    igc_file: &IgcFile,
+    relative_path: PathBuf,
+    file_path: &Path,
+) -> Result<ManifestEntry, Error> {
+    let (size, sha256) = hash_file(file_path).await?;
+    let modified = fs::metadata(file_path).await?.modified()?;
+    let downloaded_at = Timestamp::try_from(modified).unwrap_or_else(|_| Timestamp::now());
+
+    Ok(ManifestEntry::new(
+        daily_info,
+        &igc_file.callsign,
+        &igc_file.download_url,
+        relative_path,
+        size,
+        sha256,
+        downloaded_at,
+    ))
+}
+
+/// Writes the manifest of each competition to
+/// `{output}/{competition}/manifest.json`.
+async fn write_manifests(
+    output_dir: &Path,
+    manifests: BTreeMap<String, Manifest>,
+    events: impl Fn(Event<'_>),
+) -> Result<(), Error> {
+    for (competition, mut manifest) in manifests {
+        manifest.sort();
+        // The competition directory may not exist yet with a custom layout
+        let competition_dir = output_dir.join(competition);
+        fs::create_dir_all(&competition_dir).await?;
+        let path = competition_dir.join(MANIFEST_FILENAME);
+        manifest.write(&path).await?;
+        events(Event::ManifestWritten(&path));
+    }
+
+    Ok(())
+}
+
+/// Writes one results file per class and day into its directory below the
+/// output directory, and a combined results file per competition.
+///
+/// The combined file is built from the results files of all days in the
+/// competition directory, so that it still covers the whole competition
+/// after a run that only loaded some classes or days.
+async fn write_exports(
+    output_dir: &Path,
+    exports: Vec<(DailyUrlInfo, PathBuf, Vec<ResultRecord>)>,
+    format: ExportFormat,
+    events: impl Fn(Event<'_>),
+) -> Result<(), Error> {
+    let filename = format!("results.{}", format.extension());
+
+    let mut competitions = BTreeSet::new();
+    for (daily_info, day_dir, records) in exports {
+        let path = output_dir.join(day_dir).join(&filename);
+        write_results(&path, &records, format).await?;
+        events(Event::Exported(&path));
+        competitions.insert(daily_info.competition);
+    }
+
+    for competition in competitions {
+        let competition_dir = output_dir.join(competition);
+        let (records, skipped) = read_competition_results(&competition_dir, format).await?;
+        for (path, error) in &skipped {
+            events(Event::InvalidResults { path, error });
+        }
+        let path = competition_dir.join(&filename);
+        write_results(&path, &records, format).await?;
+        events(Event::Exported(&path));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jiff::civil::Date;
+
+    fn daily_info() -> DailyUrlInfo {
+        DailyUrlInfo {
+            language: "en_gb".to_string(),
+            competition: "test-competition".to_string(),
+            class: "club".to_string(),
+            date: Date::constant(2025, 6, 19),
+            task_name: "task-10".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_manifest_path() {
+        let path = Path::new("test-competition/club/2025-06-19/56J_AM.igc");
+        assert_eq!(
+            manifest_path(&daily_info(), path),
+            Path::new("club/2025-06-19/56J_AM.igc")
+        );
+        let path = Path::new("flights/2025-06-19_AM.igc");
+        assert_eq!(
+            manifest_path(&daily_info(), path),
+            Path::new("../flights/2025-06-19_AM.igc")
+        );
+
+        for path in ["test-competition/club/56J_AM.igc", "flights/56J_AM.igc"] {
+            let path = Path::new(path);
+            let relative_path = manifest_path(&daily_info(), path);
+            assert_eq!(output_path(&daily_info(), &relative_path), path);
+        }
+    }
+
+    #[test]
+    fn test_rename_to_long_filename() {
+        let dir = tempfile::tempdir().unwrap();
+        let long_filename = IgcFilename {
+            date: Date::constant(2025, 6, 19),
+            manufacturer: "LXV".to_string(),
+            serial: "ABC".to_string(),
+            flight_number: 1,
+        };
+
+        let mut filenames = vec![];
+        for callsign in ["AM", "A3"] {
+            let file_path = dir.path().join(format!("56J_{callsign}.igc"));
+            std::fs::write(&file_path, callsign).unwrap();
+            let path = rename_to_long_filename(&file_path, long_filename.clone()).unwrap();
+            assert!(!file_path.exists());
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), callsign);
+            filenames.push(path.file_name().unwrap().to_string_lossy().into_owned());
+        }
+
+        // The second flight of the logger gets the next flight number
+        insta::assert_debug_snapshot!(filenames, @r#"
+        [
+            "2025-06-19-LXV-ABC-01.igc",
+            "2025-06-19-LXV-ABC-02.igc",
+        ]
+        "#);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::igc::IgcValidationError;
+use reqwest::StatusCode;
+use std::path::PathBuf;
+
+/// Errors that can occur while resolving, scraping or downloading from
+/// SoaringSpot.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The URL is malformed or does not point to a supported SoaringSpot page.
+    #[error("{0}")]
+    InvalidUrl(String),
+
+    /// The URL points to a host other than the one of the base URL, which
+    /// is SoaringSpot by default.
+    #[error("URL must be from the same host as {base_url}, not {host}")]
+    UnsupportedHost { host: String, base_url: String },
+
+    /// The layout template for the downloaded files is invalid.
+    #[error("Invalid layout: {0}")]
+    InvalidLayout(String),
+
+    /// A date in a URL or page could not be parsed.
+    #[error("Failed to parse date '{value}': {source}")]
+    InvalidDate {
+        value: String,
+        #[source]
+        source: jiff::Error,
+    },
+
+    /// The server responded with a non-success HTTP status.
+    #[error("HTTP error {status}: {url}")]
+    HttpStatus { status: StatusCode, url: String },
+
+    /// The server kept rejecting requests with "Too Many Requests".
+    #[error("Exceeded maximum retries ({retries}) for Too Many Requests: {url}")]
+    RateLimited { url: String, retries: u32 },
+
+    /// The page markup did not have the expected structure, which usually
+    /// means that SoaringSpot changed their HTML.
+    #[error("Unexpected HTML structure: {0}")]
+    HtmlStructure(String),
+
+    /// The downloaded content is not an IGC file. The content was saved to
+    /// `path` for inspection.
+    #[error("Invalid IGC file from {url} (saved as {}): {source}", path.display())]
+    InvalidIgc {
+        url: String,
+        path: PathBuf,
+        #[source]
+        source: IgcValidationError,
+    },
+
+    /// The HTTP request failed before a response was received.
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+
+    /// Reading or writing a local file or directory failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// A CSV file could not be written or read back, like an exported
+    /// results file.
+    #[error("Failed to read or write CSV: {0}")]
+    Csv(#[from] csv::Error),
+
+    /// JSON could not be written or read back, like the manifest, an
+    /// exported results file or the output of the inspection commands.
+    #[error("Failed to read or write JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl Error {
+    /// Returns `true` for errors that are likely to go away when the request
+    /// is retried, like network errors or HTTP 5xx responses.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::HttpStatus { status, .. } => status.is_server_error(),
+            Error::Request(error) => {
+                error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,426 @@
+use crate::error::Error;
+use crate::parser::{DailyResult, Penalty};
+use crate::url_utils::DailyUrlInfo;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+
+/// File formats that results can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// A single exported result row, combining the task day with a
+/// [DailyResult] and the location of the downloaded IGC file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultRecord {
+    pub competition: String,
+    pub class: String,
+    pub date: String,
+    pub task: String,
+    pub rank: Option<u32>,
+    pub status: Option<String>,
+    pub callsign: String,
+    pub pilot: String,
+    pub nationality: Option<String>,
+    pub glider: Option<String>,
+    pub handicap: Option<f64>,
+    pub start: Option<String>,
+    pub finish: Option<String>,
+    pub time: Option<String>,
+    pub speed: Option<f64>,
+    pub distance: Option<f64>,
+    pub points: Option<u32>,
+    /// Total number of penalty points
+    pub penalty_points: Option<u32>,
+    /// Penalties, separated by "; ", e.g. "Finish altitude 545m (-105)"
+    pub penalties: Option<String>,
+    pub comment: Option<String>,
+    /// Path of the IGC file relative to the results file, see
+    /// [relative_path]
+    pub igc_file: Option<String>,
+}
+
+impl ResultRecord {
+    pub fn new(daily_info: &DailyUrlInfo, result: &DailyResult, igc_file: Option<String>) -> Self {
+        Self {
+            competition: daily_info.competition.clone(),
+            class: daily_info.class.clone(),
+            date: daily_info.date.to_string(),
+            task: daily_info.task_name.clone(),
+            rank: result.rank,
+            status: result.status.clone(),
+            callsign: result.callsign.clone(),
+            pilot: result.pilot.clone(),
+            nationality: result.nationality.clone(),
+            glider: result.glider.clone(),
+            handicap: result.handicap,
+            start: result
+                .start
+                .map(|time| time.strftime("%H:%M:%S").to_string()),
+            finish: result
+                .finish
+                .map(|time| time.strftime("%H:%M:%S").to_string()),
+            time: result.time.map(format_duration),
+            speed: result.speed,
+            distance: result.distance,
+            points: result.points,
+            penalty_points: result
+                .penalties
+                .iter()
+                .filter_map(|penalty| penalty.points)
+                .reduce(|total, points| total + points),
+            penalties: format_penalties(&result.penalties),
+            comment: result.comment.clone(),
+            igc_file,
+        }
+    }
+}
+
+fn format_penalties(penalties: &[Penalty]) -> Option<String> {
+    if penalties.is_empty() {
+        return None;
+    }
+
+    let penalties = penalties
+        .iter()
+        .map(|penalty| match penalty.points {
+            Some(points) => format!("{} (-{points})", penalty.reason),
+            None => penalty.reason.clone(),
+        })
+        .collect::<Vec<_>>();
+    Some(penalties.join("; "))
+}
+
+/// Formats a task time like SoaringSpot does, e.g. "4:18:18".
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Serializes `records` in the given format.
+pub fn export_results(records: &[ResultRecord], format: ExportFormat) -> Result<Vec<u8>, Error> {
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for record in records {
+                writer.serialize(record)?;
+            }
+            writer
+                .into_inner()
+                .map_err(|error| Error::Io(error.into_error()))
+        }
+        ExportFormat::Json => Ok(serde_json::to_vec_pretty(records)?),
+    }
+}
+
+/// Writes `records` to `path` in the given format.
+pub async fn write_results(
+    path: &Path,
+    records: &[ResultRecord],
+    format: ExportFormat,
+) -> Result<(), Error> {
+    let content = export_results(records, format)?;
+    fs::write(path, content).await?;
+    Ok(())
+}
+
+/// Reads records that were written by [write_results].
+pub async fn read_results(path: &Path, format: ExportFormat) -> Result<Vec<ResultRecord>, Error> {
+    let content = fs::read(path).await?;
+    match format {
+        ExportFormat::Csv => csv::Reader::from_reader(content.as_slice())
+            .deserialize()
+            .map(|record| Ok(record?))
+            .collect(),
+        ExportFormat::Json => Ok(serde_json::from_slice(&content)?),
+    }
+}
+
+/// Reads the daily results files in the `{class}/{date}` directories of
+/// `competition_dir`, sorted by class and date, with the paths of the IGC
+/// files relative to `competition_dir`.
+///
+/// This includes the days that were exported by earlier runs, e.g. of other
+/// classes, so that a combined file can cover the whole competition. Files
+/// that can't be read, e.g. from an older version with other columns, are
+/// skipped and returned with their error instead.
+pub async fn read_competition_results(
+    competition_dir: &Path,
+    format: ExportFormat,
+) -> Result<(Vec<ResultRecord>, Vec<(PathBuf, Error)>), Error> {
+    let filename = format!("results.{}", format.extension());
+    let mut paths = vec![];
+    for class_dir in subdirectories(competition_dir).await? {
+        for day_dir in subdirectories(&class_dir).await? {
+            let path = day_dir.join(&filename);
+            if fs::try_exists(&path).await? {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    let mut records = vec![];
+    let mut skipped = vec![];
+    for path in paths {
+        let day_records = match read_results(&path, format).await {
+            Ok(day_records) => day_records,
+            Err(error) => {
+                skipped.push((path, error));
+                continue;
+            }
+        };
+
+        let day_dir = path.parent().unwrap_or(competition_dir);
+        let day_dir = day_dir.strip_prefix(competition_dir).unwrap_or(day_dir);
+        for mut record in day_records {
+            record.igc_file = record
+                .igc_file
+                .map(|igc_file| relative_path(Path::new(""), &day_dir.join(igc_file)));
+            records.push(record);
+        }
+    }
+    Ok((records, skipped))
+}
+
+/// Returns the location of `path` as seen from the directory `dir`, where
+/// both are relative to the same directory, e.g. the output directory.
+///
+/// The components are separated by "/" on all platforms, so that exported
+/// files can be shared, e.g. "../../flights/2025-06-19_AM.igc".
+pub fn relative_path(dir: &Path, path: &Path) -> String {
+    let dir = normalize(dir);
+    let path = normalize(path);
+    let common = dir
+        .iter()
+        .zip(&path)
+        .take_while(|(dir, path)| dir == path && *dir != "..")
+        .count();
+
+    let parents = dir.len() - common;
+    let components = std::iter::repeat_n("..".to_string(), parents).chain(path[common..].to_vec());
+    components.collect::<Vec<_>>().join("/")
+}
+
+/// Returns the components of a relative path, with the `..` components
+/// resolved where possible.
+fn normalize(path: &Path) -> Vec<String> {
+    let mut components: Vec<String> = vec![];
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if components.last().is_some_and(|last| last != "..") => {
+                components.pop();
+            }
+            component => components.push(component.as_os_str().to_string_lossy().into_owned()),
+        }
+    }
+    components
+}
+
+async fn subdirectories(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut entries = fs::read_dir(dir).await?;
+    let mut dirs = vec![];
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+    Ok(dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_daily_result_table;
+    use crate::url_utils::default_base_url;
+    use jiff::civil::Date;
+
+    fn records() -> Vec<ResultRecord> {
+        let daily_info = DailyUrlInfo {
+            language: "en_gb".to_string(),
+            competition: "39th-fai-world-gliding-championships-tabor-2025".to_string(),
+            class: "club".to_string(),
+            date: Date::constant(2025, 6, 19),
+            task_name: "task-10".to_string(),
+        };
+
+        let html = include_str!("../tests/fixtures/day.html");
+        let results = parse_daily_result_table(html, &default_base_url()).unwrap();
+        results
+            .iter()
+            .take(2)
+            .chain(results.iter().find(|result| result.callsign == "CX"))
+            .chain(results.last())
+            .map(|result| {
+                let igc_file = result
+                    .download_url
+                    .as_ref()
+                    .map(|_| format!("56J_{}.igc", result.callsign));
+                ResultRecord::new(&daily_info, result, igc_file)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let csv = export_results(&records(), ExportFormat::Csv).unwrap();
+        insta::assert_snapshot!(String::from_utf8(csv).unwrap(), @r"
+        competition,class,date,task,rank,status,callsign,pilot,nationality,glider,handicap,start,finish,time,speed,distance,points,penalty_points,penalties,comment,igc_file
+        39th-fai-world-gliding-championships-tabor-2025,club,2025-06-19,task-10,1,,AM,John Smith,LT,LS 3,1.062,12:23:59,16:42:17,4:18:18,100.66,433.33,1000,,,,56J_AM.igc
+        39th-fai-world-gliding-championships-tabor-2025,club,2025-06-19,task-10,2,,A3,Jane Doe,CZ,ASW 20,1.071,12:24:49,16:42:34,4:17:45,100.87,433.33,991,,,,56J_A3.igc
+        39th-fai-world-gliding-championships-tabor-2025,club,2025-06-19,task-10,27,,CX,Yasmin Reed,CZ,ASW 20,1.068,12:24:36,17:14:54,4:50:18,89.56,433.33,738,105,Finish altitude 545m (-105),Finish altitude 545m (-105),56J_CX.igc
+        39th-fai-world-gliding-championships-tabor-2025,club,2025-06-19,task-10,,DNF,CF,Nora Reed,SI,DG 300,1.022,,,,,0.0,0,,,DNF,
+        ");
+    }
+
+    #[test]
+    fn test_export_json() {
+        let json = export_results(&records()[..1], ExportFormat::Json).unwrap();
+        insta::assert_snapshot!(String::from_utf8(json).unwrap(), @r#"
+        [
+          {
+            "competition": "39th-fai-world-gliding-championships-tabor-2025",
+            "class": "club",
+            "date": "2025-06-19",
+            "task": "task-10",
+            "rank": 1,
+            "status": null,
+            "callsign": "AM",
+            "pilot": "John Smith",
+            "nationality": "LT",
+            "glider": "LS 3",
+            "handicap": 1.062,
+            "start": "12:23:59",
+            "finish": "16:42:17",
+            "time": "4:18:18",
+            "speed": 100.66,
+            "distance": 433.33,
+            "points": 1000,
+            "penalty_points": null,
+            "penalties": null,
+            "comment": null,
+            "igc_file": "56J_AM.igc"
+          }
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_relative_path() {
+        let dir = Path::new("competition/club/2025-06-19");
+        let paths = [
+            "competition/club/2025-06-19/56J_AM.igc",
+            "competition/56J_AM.igc",
+            "flights/2025-06-19_AM.igc",
+            "competition/club/2025-06-19/../2025-06-18/56I_AM.igc",
+        ];
+        let relative_paths = paths
+            .iter()
+            .map(|path| relative_path(dir, Path::new(path)))
+            .collect::<Vec<_>>();
+        insta::assert_debug_snapshot!(relative_paths, @r#"
+        [
+            "56J_AM.igc",
+            "../../56J_AM.igc",
+            "../../../flights/2025-06-19_AM.igc",
+            "../2025-06-18/56I_AM.igc",
+        ]
+        "#);
+        assert_eq!(
+            relative_path(
+                Path::new(""),
+                Path::new("club/2025-06-19/../../../flights/AM.igc")
+            ),
+            "../flights/AM.igc"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_results() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in [ExportFormat::Csv, ExportFormat::Json] {
+            let path = dir.path().join(format!("results.{}", format.extension()));
+            write_results(&path, &records(), format).await.unwrap();
+            assert_eq!(read_results(&path, format).await.unwrap(), records());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_competition_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let days = [
+            ("standard", "2025-06-18"),
+            ("club", "2025-06-19"),
+            ("club", "2025-06-18"),
+        ];
+        for (class, date) in days {
+            let records = records()[..2]
+                .iter()
+                .map(|record| ResultRecord {
+                    class: class.to_string(),
+                    date: date.to_string(),
+                    ..record.clone()
+                })
+                .collect::<Vec<_>>();
+            let day_dir = dir.path().join(class).join(date);
+            std::fs::create_dir_all(&day_dir).unwrap();
+            write_results(&day_dir.join("results.csv"), &records, ExportFormat::Csv)
+                .await
+                .unwrap();
+        }
+        // Other files and directories are ignored, and invalid files skipped
+        std::fs::create_dir_all(dir.path().join("club/2025-06-20")).unwrap();
+        let invalid_path = dir.path().join("club/2025-06-17/results.csv");
+        std::fs::create_dir_all(invalid_path.parent().unwrap()).unwrap();
+        std::fs::write(&invalid_path, "callsign,points\nAM,abc\n").unwrap();
+        write_results(
+            &dir.path().join("results.csv"),
+            &records(),
+            ExportFormat::Csv,
+        )
+        .await
+        .unwrap();
+
+        let (records, skipped) = read_competition_results(dir.path(), ExportFormat::Csv)
+            .await
+            .unwrap();
+        let skipped = skipped.iter().map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(skipped, [&invalid_path]);
+        let rows = records
+            .iter()
+            .map(|record| {
+                let igc_file = record.igc_file.as_deref().unwrap_or_default();
+                format!("{} {} {}", record.class, record.date, igc_file)
+            })
+            .collect::<Vec<_>>();
+        insta::assert_debug_snapshot!(rows, @r#"
+        [
+            "club 2025-06-18 club/2025-06-18/56J_AM.igc",
+            "club 2025-06-18 club/2025-06-18/56J_A3.igc",
+            "club 2025-06-19 club/2025-06-19/56J_AM.igc",
+            "club 2025-06-19 club/2025-06-19/56J_A3.igc",
+            "standard 2025-06-18 standard/2025-06-18/56J_AM.igc",
+            "standard 2025-06-18 standard/2025-06-18/56J_A3.igc",
+        ]
+        "#);
+    }
+}
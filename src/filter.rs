@@ -0,0 +1,122 @@
+use crate::parser::DailyResult;
+
+/// Selects rows of the daily results, e.g. to only download the flights of
+/// a national team or a few competitors.
+///
+/// A row matches if it matches every criterion that is set, and it matches
+/// a criterion if it matches any of its values. An empty filter matches all
+/// rows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResultFilter {
+    /// Callsigns like "KK", compared case-insensitively
+    pub callsigns: Vec<String>,
+    /// Country codes of the nationality like "GB", compared case-insensitively
+    pub nations: Vec<String>,
+    /// Parts of the glider type like "ASG 29", ignoring case and spaces
+    pub gliders: Vec<String>,
+    /// Parts of the pilot name, ignoring case
+    pub pilots: Vec<String>,
+}
+
+impl ResultFilter {
+    pub fn is_empty(&self) -> bool {
+        self.callsigns.is_empty()
+            && self.nations.is_empty()
+            && self.gliders.is_empty()
+            && self.pilots.is_empty()
+    }
+
+    pub fn matches(&self, result: &DailyResult) -> bool {
+        let nationality = result.nationality.as_deref().unwrap_or_default();
+        let glider = result.glider.as_deref().unwrap_or_default();
+
+        matches_any(&self.callsigns, |callsign| {
+            callsign.eq_ignore_ascii_case(&result.callsign)
+        }) && matches_any(&self.nations, |nation| {
+            nation.eq_ignore_ascii_case(nationality)
+        }) && matches_any(&self.gliders, |part| {
+            normalize_glider(glider).contains(&normalize_glider(part))
+        }) && matches_any(&self.pilots, |part| {
+            result.pilot.to_lowercase().contains(&part.to_lowercase())
+        })
+    }
+}
+
+/// Returns `true` if no `values` are given or any of them matches.
+fn matches_any(values: &[String], matches: impl Fn(&str) -> bool) -> bool {
+    values.is_empty() || values.iter().any(|value| matches(value))
+}
+
+/// Normalizes a glider type, so that "ASG 29" matches "ASG29" and "asg 29".
+fn normalize_glider(glider: &str) -> String {
+    glider
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_daily_result_table;
+    use crate::url_utils::default_base_url;
+
+    fn callsigns(filter: &ResultFilter) -> Vec<String> {
+        let html = include_str!("../tests/fixtures/day.html");
+        parse_daily_result_table(html, &default_base_url())
+            .unwrap()
+            .into_iter()
+            .filter(|result| filter.matches(result))
+            .map(|result| result.callsign)
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_filter() {
+        let filter = ResultFilter::default();
+        assert!(filter.is_empty());
+        assert_eq!(callsigns(&filter).len(), 42);
+    }
+
+    #[test]
+    fn test_filter_by_callsign() {
+        let filter = ResultFilter {
+            callsigns: vec!["am".to_string(), "A3".to_string(), "XX".to_string()],
+            ..ResultFilter::default()
+        };
+        insta::assert_debug_snapshot!(callsigns(&filter), @r#"
+        [
+            "AM",
+            "A3",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_filter_by_nation_and_glider() {
+        let filter = ResultFilter {
+            nations: vec!["fr".to_string(), "PL".to_string()],
+            gliders: vec!["ls4".to_string(), "ASW 20".to_string()],
+            ..ResultFilter::default()
+        };
+        insta::assert_debug_snapshot!(callsigns(&filter), @r#"
+        [
+            "XI",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_filter_by_pilot() {
+        let filter = ResultFilter {
+            pilots: vec!["smith".to_string()],
+            ..ResultFilter::default()
+        };
+        insta::assert_debug_snapshot!(callsigns(&filter), @r#"
+        [
+            "AM",
+        ]
+        "#);
+    }
+}
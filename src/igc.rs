@@ -0,0 +1,389 @@
+use jiff::civil::Date;
+use std::fmt;
+
+/// Reasons why content was rejected as an IGC flight log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgcValidationError {
+    /// The content is empty.
+    Empty,
+    /// The first record is not an A record, e.g. because the content is an
+    /// HTML page.
+    MissingARecord,
+    /// The content does not contain any B (fix) records.
+    MissingBRecords,
+    /// The content contains a carriage return that is not followed by a
+    /// line feed.
+    InvalidLineEnding,
+    /// The content contains NUL bytes.
+    Binary,
+}
+
+impl fmt::Display for IgcValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            IgcValidationError::Empty => "file is empty",
+            IgcValidationError::MissingARecord => "file does not start with an A record",
+            IgcValidationError::MissingBRecords => "file does not contain any B records",
+            IgcValidationError::InvalidLineEnding => "file contains invalid line endings",
+            IgcValidationError::Binary => "file contains binary data",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for IgcValidationError {}
+
+/// Incrementally checks that a byte stream looks like an IGC flight log.
+///
+/// The content is fed in chunks via [IgcValidator::update], so that
+/// downloads can be validated while they are streamed to disk.
+#[derive(Debug)]
+pub struct IgcValidator {
+    at_line_start: bool,
+    pending_cr: bool,
+    first_record: Option<u8>,
+    has_b_record: bool,
+    error: Option<IgcValidationError>,
+}
+
+impl Default for IgcValidator {
+    fn default() -> Self {
+        Self {
+            at_line_start: true,
+            pending_cr: false,
+            first_record: None,
+            has_b_record: false,
+            error: None,
+        }
+    }
+}
+
+impl IgcValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            if self.error.is_some() {
+                return;
+            }
+
+            if self.pending_cr && byte != b'\n' {
+                self.error = Some(IgcValidationError::InvalidLineEnding);
+                return;
+            }
+
+            match byte {
+                b'\r' => self.pending_cr = true,
+                b'\n' => {
+                    self.pending_cr = false;
+                    self.at_line_start = true;
+                }
+                b'\0' => self.error = Some(IgcValidationError::Binary),
+                _ if self.at_line_start => {
+                    self.at_line_start = false;
+
+                    // The first record of the file must be the A record
+                    if self.first_record.is_none() {
+                        self.first_record = Some(byte);
+                        if byte != b'A' {
+                            self.error = Some(IgcValidationError::MissingARecord);
+                        }
+                    }
+
+                    if byte == b'B' {
+                        self.has_b_record = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<(), IgcValidationError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        if self.first_record.is_none() {
+            return Err(IgcValidationError::Empty);
+        }
+
+        if self.pending_cr {
+            return Err(IgcValidationError::InvalidLineEnding);
+        }
+
+        if !self.has_b_record {
+            return Err(IgcValidationError::MissingBRecords);
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that `content` looks like an IGC flight log.
+///
+/// This verifies that the first record is an A record, that there is at
+/// least one B record, and that lines end with either CRLF or LF.
+pub fn validate_igc(content: &[u8]) -> Result<(), IgcValidationError> {
+    let mut validator = IgcValidator::new();
+    validator.update(content);
+    validator.finish()
+}
+
+/// Flight metadata from the A and H records of an IGC file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IgcHeader {
+    /// Three-character logger manufacturer code from the A record
+    pub manufacturer: Option<String>,
+    /// Logger serial number from the A record
+    pub logger_serial: Option<String>,
+    /// Flight date from the HFDTE record
+    pub flight_date: Option<Date>,
+    pub pilot: Option<String>,
+    pub glider_type: Option<String>,
+    pub registration: Option<String>,
+    pub competition_id: Option<String>,
+    pub competition_class: Option<String>,
+}
+
+impl IgcHeader {
+    /// Parses the A and H records at the start of an IGC file.
+    ///
+    /// Parsing stops at the first B record, so it is fine to pass only the
+    /// first part of a file. Unknown or malformed records are ignored.
+    pub fn parse(content: &[u8]) -> Self {
+        let content = String::from_utf8_lossy(content);
+        let mut header = IgcHeader::default();
+
+        for line in content.lines() {
+            let line = line.trim_end();
+            match line.as_bytes().first() {
+                Some(b'A') => {
+                    header.manufacturer = line.get(1..4).map(str::to_string);
+                    header.logger_serial = line.get(4..7).map(str::to_string);
+                }
+                Some(b'H') => header.parse_h_record(line),
+                Some(b'B') => break,
+                _ => {}
+            }
+        }
+
+        header
+    }
+
+    fn parse_h_record(&mut self, line: &str) {
+        // H{source}{subtype}{long name}:{value}, e.g. "HFPLTPILOTINCHARGE:John Smith"
+        let Some(subtype) = line.get(2..5) else {
+            return;
+        };
+        let rest = &line[5..];
+        let value = rest.split_once(':').map_or(rest, |(_, value)| value).trim();
+        let value = (!value.is_empty()).then(|| value.to_string());
+
+        match subtype {
+            "DTE" => self.flight_date = parse_dte(rest),
+            "PLT" => self.pilot = value,
+            "GTY" => self.glider_type = value,
+            "GID" => self.registration = value,
+            "CID" => self.competition_id = value,
+            "CCL" => self.competition_class = value,
+            _ => {}
+        }
+    }
+
+    /// Compares the header against the competition ID and date that the
+    /// flight was listed under on SoaringSpot.
+    ///
+    /// Fields that are missing from the header are not reported.
+    pub fn check(&self, competition_id: &str, date: Date) -> Vec<HeaderMismatch> {
+        let mut mismatches = vec![];
+
+        if let Some(flight_date) = self.flight_date
+            && flight_date != date
+        {
+            mismatches.push(HeaderMismatch::Date {
+                expected: date,
+                found: flight_date,
+            });
+        }
+
+        if let Some(found) = &self.competition_id
+            && !found.eq_ignore_ascii_case(competition_id.trim())
+        {
+            mismatches.push(HeaderMismatch::CompetitionId {
+                expected: competition_id.to_string(),
+                found: found.clone(),
+            });
+        }
+
+        mismatches
+    }
+}
+
+/// Parses the date of an HFDTE record, which is either `DDMMYY` or
+/// `DATE:DDMMYY,NN`.
+fn parse_dte(value: &str) -> Option<Date> {
+    let value = value.split_once(':').map_or(value, |(_, value)| value);
+    let digits = value.trim().get(..6)?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let day = digits[0..2].parse().ok()?;
+    let month = digits[2..4].parse().ok()?;
+    let year: i16 = digits[4..6].parse().ok()?;
+    let year = if year >= 80 { 1900 + year } else { 2000 + year };
+
+    Date::new(year, month, day).ok()
+}
+
+/// A difference between an IGC header and the SoaringSpot results.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderMismatch {
+    /// The HFDTE date does not match the day the flight was listed under.
+    Date { expected: Date, found: Date },
+    /// The HFCID competition ID does not match the callsign on SoaringSpot.
+    CompetitionId { expected: String, found: String },
+}
+
+impl fmt::Display for HeaderMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderMismatch::Date { expected, found } => {
+                write!(f, "flight date {found} does not match task date {expected}")
+            }
+            HeaderMismatch::CompetitionId { expected, found } => {
+                write!(
+                    f,
+                    "competition ID '{found}' does not match callsign '{expected}'"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IGC: &str = "AXXX001\r\nHFDTE190625\r\nB1200004900000N01400000EA0050000500\r\n";
+
+    #[test]
+    fn test_validate_igc() {
+        assert_eq!(validate_igc(IGC.as_bytes()), Ok(()));
+
+        // LF line endings are accepted too
+        let igc = IGC.replace("\r\n", "\n");
+        assert_eq!(validate_igc(igc.as_bytes()), Ok(()));
+
+        // Chunk boundaries don't matter
+        let mut validator = IgcValidator::new();
+        for chunk in IGC.as_bytes().chunks(3) {
+            validator.update(chunk);
+        }
+        assert_eq!(validator.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_igc_errors() {
+        assert_eq!(validate_igc(b""), Err(IgcValidationError::Empty));
+        assert_eq!(validate_igc(b"\r\n\r\n"), Err(IgcValidationError::Empty));
+
+        let html = "<!DOCTYPE html>\n<html><h1>Too Many Requests</h1></html>\n";
+        assert_eq!(
+            validate_igc(html.as_bytes()),
+            Err(IgcValidationError::MissingARecord)
+        );
+
+        let igc = "AXXX001\r\nHFDTE190625\r\n";
+        assert_eq!(
+            validate_igc(igc.as_bytes()),
+            Err(IgcValidationError::MissingBRecords)
+        );
+
+        let igc = IGC.replace("\r\n", "\r");
+        assert_eq!(
+            validate_igc(igc.as_bytes()),
+            Err(IgcValidationError::InvalidLineEnding)
+        );
+
+        let igc = format!("{IGC}\0\0\0");
+        assert_eq!(
+            validate_igc(igc.as_bytes()),
+            Err(IgcValidationError::Binary)
+        );
+    }
+
+    const HEADER: &str = "\
+AFLA6NG\r
+HFDTEDATE:190625,01\r
+HFFXA035\r
+HFPLTPILOTINCHARGE:John Smith\r
+HFCM2CREW2:NIL\r
+HFGTYGLIDERTYPE:LS 3\r
+HFGIDGLIDERID:D-1234\r
+HFDTM100GPSDATUM:WGS-1984\r
+HFCIDCOMPETITIONID:AM\r
+HFCCLCOMPETITIONCLASS:Club\r
+B1200004900000N01400000EA0050000500\r
+HFPLTPILOTINCHARGE:Ignored\r
+";
+
+    #[test]
+    fn test_parse_igc_header() {
+        let header = IgcHeader::parse(HEADER.as_bytes());
+        insta::assert_debug_snapshot!(header, @r#"
+        IgcHeader {
+            manufacturer: Some(
+                "FLA",
+            ),
+            logger_serial: Some(
+                "6NG",
+            ),
+            flight_date: Some(
+                2025-06-19,
+            ),
+            pilot: Some(
+                "John Smith",
+            ),
+            glider_type: Some(
+                "LS 3",
+            ),
+            registration: Some(
+                "D-1234",
+            ),
+            competition_id: Some(
+                "AM",
+            ),
+            competition_class: Some(
+                "Club",
+            ),
+        }
+        "#);
+
+        // Old style date record without the long name
+        let header = IgcHeader::parse(b"AXXXABC\r\nHFDTE010199\r\nHFPLTPILOT:\r\n");
+        assert_eq!(header.flight_date, Some(Date::constant(1999, 1, 1)));
+        assert_eq!(header.pilot, None);
+    }
+
+    #[test]
+    fn test_igc_header_check() {
+        let header = IgcHeader::parse(HEADER.as_bytes());
+        assert_eq!(header.check("am", Date::constant(2025, 6, 19)), vec![]);
+
+        let mismatches = header.check("A3", Date::constant(2025, 6, 20));
+        let mismatches = mismatches
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        insta::assert_debug_snapshot!(mismatches, @r#"
+        [
+            "flight date 2025-06-19 does not match task date 2025-06-20",
+            "competition ID 'AM' does not match callsign 'A3'",
+        ]
+        "#);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::igc::IgcHeader;
+use jiff::Zoned;
+use jiff::civil::Date;
+
+/// Single character manufacturer codes of short IGC filenames and their
+/// three character equivalents from the A record, from the manufacturer
+/// table of the IGC specification.
+///
+/// Newer manufacturers only have a three character code, so their files
+/// always have long names.
+const MANUFACTURERS: [(char, &str); 20] = [
+    ('A', "GCS"),
+    ('C', "CAM"),
+    ('D', "DSX"),
+    ('E', "EWA"),
+    ('F', "FIL"),
+    ('G', "FLA"),
+    ('H', "SCH"),
+    ('I', "ACT"),
+    ('K', "NKL"),
+    ('L', "LXN"),
+    ('M', "IMI"),
+    ('N', "NTE"),
+    ('P', "PES"),
+    ('R', "PRT"),
+    ('S', "SDI"),
+    ('T', "TRI"),
+    ('V', "LXV"),
+    ('W', "WES"),
+    ('X', "XXX"),
+    ('Z', "ZAN"),
+];
+
+/// The information in an IGC filename, following the naming convention of
+/// the IGC specification.
+///
+/// Long filenames look like `2025-06-19-LXV-ABC-01.igc` and short ones like
+/// `56JVABC1.igc`, with the year, month, day and flight number encoded in
+/// single characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgcFilename {
+    pub date: Date,
+    /// Three character manufacturer code, or the single character code of
+    /// a short filename if it is unknown
+    pub manufacturer: String,
+    /// Serial number of the logger
+    pub serial: String,
+    /// Number of the flight of the logger on that day, starting at 1
+    pub flight_number: u32,
+}
+
+impl IgcFilename {
+    /// Returns the long filename for the first flight of the logger in the
+    /// A record of `header` on `date`, if the record is present.
+    pub fn from_header(header: &IgcHeader, date: Date) -> Option<Self> {
+        let manufacturer = header.manufacturer.as_ref()?;
+        let serial = header.logger_serial.as_ref()?;
+        let is_code =
+            |code: &str| !code.is_empty() && code.bytes().all(|b| b.is_ascii_alphanumeric());
+        if !is_code(manufacturer) || !is_code(serial) {
+            return None;
+        }
+
+        Some(Self {
+            date,
+            manufacturer: manufacturer.to_ascii_uppercase(),
+            serial: serial.to_ascii_uppercase(),
+            flight_number: 1,
+        })
+    }
+
+    /// Parses a long or short IGC filename, ignoring the case of the name
+    /// and the extension.
+    ///
+    /// Short filenames only contain the last digit of the year, which is
+    /// read as the most recent matching date up to today.
+    pub fn parse(filename: &str) -> Option<Self> {
+        Self::parse_before(filename, Zoned::now().date())
+    }
+
+    /// Like [IgcFilename::parse], but reads the year of short filenames as
+    /// the most recent matching date up to `latest`, e.g. the date of the
+    /// competition.
+    pub fn parse_before(filename: &str, latest: Date) -> Option<Self> {
+        let (stem, extension) = filename.rsplit_once('.')?;
+        if !extension.eq_ignore_ascii_case("igc") {
+            return None;
+        }
+        Self::parse_long(stem).or_else(|| Self::parse_short(stem, latest))
+    }
+
+    fn parse_long(stem: &str) -> Option<Self> {
+        let [year, month, day, manufacturer, serial, flight_number] =
+            stem.split('-').collect::<Vec<_>>().try_into().ok()?;
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 || flight_number.len() != 2 {
+            return None;
+        }
+        let is_code = |code: &str| code.bytes().all(|b| b.is_ascii_alphanumeric());
+        if manufacturer.len() != 3
+            || !is_code(manufacturer)
+            || serial.is_empty()
+            || !is_code(serial)
+        {
+            return None;
+        }
+
+        let date = Date::new(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?).ok()?;
+        Some(Self {
+            date,
+            manufacturer: manufacturer.to_ascii_uppercase(),
+            serial: serial.to_ascii_uppercase(),
+            flight_number: flight_number.parse().ok()?,
+        })
+    }
+
+    /// Parses a short filename like `56JVABC1`.
+    fn parse_short(stem: &str, latest: Date) -> Option<Self> {
+        let chars = stem
+            .chars()
+            .map(|c| c.to_ascii_uppercase())
+            .collect::<Vec<_>>();
+        let [year, month, day, manufacturer, serial @ .., flight_number] = chars.as_slice() else {
+            return None;
+        };
+        if serial.len() != 3 || !serial.iter().all(char::is_ascii_alphanumeric) {
+            return None;
+        }
+
+        let year_digit = year.to_digit(10)? as i16;
+        let month = month.to_digit(36).filter(|month| *month >= 1)? as i8;
+        let day = day.to_digit(36).filter(|day| *day >= 1)? as i8;
+
+        // The last year with that digit, or the decade before if the date
+        // would be later than `latest`
+        let year = latest.year() - (latest.year() - year_digit).rem_euclid(10);
+        let date = match Date::new(year, month, day).ok()? {
+            date if date > latest => Date::new(year - 10, month, day).ok()?,
+            date => date,
+        };
+
+        let manufacturer = MANUFACTURERS
+            .iter()
+            .find(|(code, _)| code == manufacturer)
+            .map_or(manufacturer.to_string(), |(_, name)| name.to_string());
+
+        Some(Self {
+            date,
+            manufacturer,
+            serial: serial.iter().collect(),
+            flight_number: flight_number.to_digit(36).filter(|number| *number >= 1)?,
+        })
+    }
+
+    /// Returns the long filename like `2025-06-19-LXV-ABC-01.igc`.
+    pub fn to_long(&self) -> String {
+        format!(
+            "{}-{}-{}-{:02}.igc",
+            self.date.strftime("%Y-%m-%d"),
+            self.manufacturer,
+            self.serial,
+            self.flight_number
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_long_filename() {
+        insta::assert_debug_snapshot!(IgcFilename::parse("2025-06-19-LXV-ABC-01.IGC"), @r#"
+        Some(
+            IgcFilename {
+                date: 2025-06-19,
+                manufacturer: "LXV",
+                serial: "ABC",
+                flight_number: 1,
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(IgcFilename::parse("2025-06-19-fla-1A2B3C-12.igc"), @r#"
+        Some(
+            IgcFilename {
+                date: 2025-06-19,
+                manufacturer: "FLA",
+                serial: "1A2B3C",
+                flight_number: 12,
+            },
+        )
+        "#);
+
+        assert_eq!(IgcFilename::parse("2025-06-19-LXV-ABC-01.txt"), None);
+        assert_eq!(IgcFilename::parse("2025-13-19-LXV-ABC-01.igc"), None);
+        assert_eq!(IgcFilename::parse("2025-06-19-LX-ABC-01.igc"), None);
+    }
+
+    #[test]
+    fn test_parse_short_filename() {
+        let parse = |filename| IgcFilename::parse_before(filename, Date::constant(2025, 7, 1));
+        insta::assert_debug_snapshot!(parse("56JVABC1.igc"), @r#"
+        Some(
+            IgcFilename {
+                date: 2025-06-19,
+                manufacturer: "LXV",
+                serial: "ABC",
+                flight_number: 1,
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(parse("4CVQ0012.IGC"), @r#"
+        Some(
+            IgcFilename {
+                date: 2024-12-31,
+                manufacturer: "Q",
+                serial: "001",
+                flight_number: 2,
+            },
+        )
+        "#);
+
+        assert_eq!(parse("56J_AM.igc"), None);
+        assert_eq!(parse("5D1VABC1.igc"), None);
+    }
+
+    #[test]
+    fn test_short_filename_year() {
+        let date = |filename, latest| {
+            let filename = IgcFilename::parse_before(filename, latest).unwrap();
+            (filename.date, filename.manufacturer)
+        };
+        let latest = Date::constant(2025, 7, 1);
+        assert_eq!(
+            date("56JHABC1.igc", latest),
+            (Date::constant(2025, 6, 19), "SCH".to_string())
+        );
+        // Later in the year than `latest` is from the decade before
+        assert_eq!(date("58JMABC1.igc", latest).0, Date::constant(2015, 8, 19));
+        assert_eq!(date("96JKABC1.igc", latest).0, Date::constant(2019, 6, 19));
+        assert_eq!(
+            date("06JLABC1.igc", Date::constant(2031, 1, 1)).0,
+            Date::constant(2030, 6, 19)
+        );
+    }
+
+    #[test]
+    fn test_long_filename_roundtrip() {
+        let filename = "2025-06-19-LXV-ABC-01.igc";
+        assert_eq!(IgcFilename::parse(filename).unwrap().to_long(), filename);
+    }
+
+    #[test]
+    fn test_from_header() {
+        let header = IgcHeader::parse(b"ALXVABC\r\nHFDTE190625\r\n");
+        let filename = IgcFilename::from_header(&header, Date::constant(2025, 6, 19)).unwrap();
+        assert_eq!(filename.to_long(), "2025-06-19-LXV-ABC-01.igc");
+
+        let header = IgcHeader::parse(b"HFDTE190625\r\n");
+        assert_eq!(
+            IgcFilename::from_header(&header, Date::constant(2025, 6, 19)),
+            None
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::output::TableRow;
+use crate::parser::DailyResult;
+use crate::url_utils::DailyUrlInfo;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use url::Url;
+
+/// A class of a competition, with the number of its days.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClassSummary {
+    pub competition: String,
+    pub class: String,
+    /// Number of competition days, without practice days
+    pub days: usize,
+    pub practice_days: usize,
+    pub first_date: String,
+    pub last_date: String,
+}
+
+/// Returns the classes of `daily_urls`, in the order they first appear.
+pub fn classes(daily_urls: &[DailyUrlInfo]) -> Vec<ClassSummary> {
+    let mut classes: Vec<ClassSummary> = vec![];
+    for daily_url in daily_urls {
+        let date = daily_url.date.to_string();
+        let index = classes.iter().position(|class| {
+            class.competition == daily_url.competition && class.class == daily_url.class
+        });
+        let class = match index {
+            Some(index) => &mut classes[index],
+            None => {
+                classes.push(ClassSummary {
+                    competition: daily_url.competition.clone(),
+                    class: daily_url.class.clone(),
+                    days: 0,
+                    practice_days: 0,
+                    first_date: date.clone(),
+                    last_date: date.clone(),
+                });
+                classes.last_mut().unwrap()
+            }
+        };
+
+        match daily_url.is_practice_day() {
+            true => class.practice_days += 1,
+            false => class.days += 1,
+        }
+        class.first_date = class.first_date.clone().min(date.clone());
+        class.last_date = class.last_date.clone().max(date);
+    }
+    classes
+}
+
+impl TableRow for ClassSummary {
+    const HEADER: &'static [&'static str] = &["CLASS", "DAYS", "PRACTICE", "FIRST", "LAST"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.class.clone(),
+            self.days.to_string(),
+            self.practice_days.to_string(),
+            self.first_date.clone(),
+            self.last_date.clone(),
+        ]
+    }
+}
+
+/// A single day of a class.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DaySummary {
+    pub competition: String,
+    pub class: String,
+    pub date: String,
+    pub task: String,
+    pub practice: bool,
+    /// URL of the daily results page
+    pub url: String,
+}
+
+/// Returns the days of `daily_urls` with the URLs of their results pages.
+pub fn days(daily_urls: &[DailyUrlInfo], base_url: &Url) -> Vec<DaySummary> {
+    daily_urls
+        .iter()
+        .map(|daily_url| DaySummary {
+            competition: daily_url.competition.clone(),
+            class: daily_url.class.clone(),
+            date: daily_url.date.to_string(),
+            task: daily_url.task_name.clone(),
+            practice: daily_url.is_practice_day(),
+            url: daily_url.to_daily_url(base_url),
+        })
+        .collect()
+}
+
+impl TableRow for DaySummary {
+    const HEADER: &'static [&'static str] = &["CLASS", "DATE", "TASK", "PRACTICE", "URL"];
+
+    fn cells(&self) -> Vec<String> {
+        let practice = if self.practice { "yes" } else { "no" };
+        vec![
+            self.class.clone(),
+            self.date.clone(),
+            self.task.clone(),
+            practice.to_string(),
+            self.url.clone(),
+        ]
+    }
+}
+
+/// A contestant of a class, as listed in the daily results.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PilotSummary {
+    pub competition: String,
+    pub class: String,
+    pub callsign: String,
+    pub pilot: String,
+    pub nationality: Option<String>,
+    pub glider: Option<String>,
+    /// Number of days the contestant is listed in the results
+    pub days: usize,
+    /// Number of days with an IGC file
+    pub flights: usize,
+}
+
+/// Returns the contestants of the daily results, sorted by class and
+/// callsign.
+///
+/// The pilot, nationality and glider are taken from the last day, in case
+/// they changed during the competition.
+pub fn pilots(daily_results: &[(DailyUrlInfo, Vec<DailyResult>)]) -> Vec<PilotSummary> {
+    let mut pilots = BTreeMap::new();
+    for (daily_url, results) in daily_results {
+        for result in results {
+            let key = (
+                daily_url.competition.clone(),
+                daily_url.class.clone(),
+                result.callsign.clone(),
+            );
+            let (date, pilot) = pilots.entry(key).or_insert_with(|| {
+                let pilot = PilotSummary {
+                    competition: daily_url.competition.clone(),
+                    class: daily_url.class.clone(),
+                    callsign: result.callsign.clone(),
+                    pilot: String::new(),
+                    nationality: None,
+                    glider: None,
+                    days: 0,
+                    flights: 0,
+                };
+                (daily_url.date, pilot)
+            });
+
+            pilot.days += 1;
+            if result.download_url.is_some() {
+                pilot.flights += 1;
+            }
+            if daily_url.date >= *date {
+                *date = daily_url.date;
+                pilot.pilot = result.pilot.clone();
+                pilot.nationality = result.nationality.clone();
+                pilot.glider = result.glider.clone();
+            }
+        }
+    }
+    pilots.into_values().map(|(_, pilot)| pilot).collect()
+}
+
+impl TableRow for PilotSummary {
+    const HEADER: &'static [&'static str] = &[
+        "CLASS", "CALLSIGN", "PILOT", "NATION", "GLIDER", "DAYS", "FLIGHTS",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.class.clone(),
+            self.callsign.clone(),
+            self.pilot.clone(),
+            self.nationality.clone().unwrap_or_default(),
+            self.glider.clone().unwrap_or_default(),
+            self.days.to_string(),
+            self.flights.to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{OutputFormat, format_output};
+    use crate::parser::{parse_daily_result_table, parse_daily_results};
+    use crate::url_utils::default_base_url;
+
+    fn daily_urls() -> Vec<DailyUrlInfo> {
+        let html = include_str!("../tests/fixtures/results.html");
+        parse_daily_results(html, &default_base_url()).unwrap()
+    }
+
+    #[test]
+    fn test_classes() {
+        let classes = classes(&daily_urls());
+        insta::assert_snapshot!(format_output(&classes, OutputFormat::Table).unwrap(), @r#"
+        CLASS      DAYS  PRACTICE  FIRST       LAST
+        club       11    2         2025-06-04  2025-06-20
+        standard   11    2         2025-06-04  2025-06-20
+        -15-meter  11    2         2025-06-04  2025-06-20
+        "#);
+    }
+
+    #[test]
+    fn test_days() {
+        let days = days(&daily_urls()[9..12], &default_base_url());
+        insta::assert_snapshot!(format_output(&days, OutputFormat::Table).unwrap(), @r#"
+        CLASS  DATE        TASK        PRACTICE  URL
+        club   2025-06-10  task-2      no        https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/task-2-on-2025-06-10/daily
+        club   2025-06-09  task-1      no        https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/task-1-on-2025-06-09/daily
+        club   2025-06-06  practice-2  yes       https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/practice-2-on-2025-06-06/daily
+        "#);
+        insta::assert_snapshot!(format_output(&days[..1], OutputFormat::Json).unwrap(), @r#"
+        [
+          {
+            "competition": "39th-fai-world-gliding-championships-tabor-2025",
+            "class": "club",
+            "date": "2025-06-10",
+            "task": "task-2",
+            "practice": false,
+            "url": "https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/task-2-on-2025-06-10/daily"
+          }
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_pilots() {
+        let html = include_str!("../tests/fixtures/day.html");
+        let results = parse_daily_result_table(html, &default_base_url()).unwrap();
+        let daily_urls = daily_urls();
+        let (first_day, second_day) = (daily_urls[1].clone(), daily_urls[2].clone());
+        let daily_results = [
+            (first_day, results[..3].to_vec()),
+            (second_day, results[1..4].to_vec()),
+        ];
+        let pilots = pilots(&daily_results);
+        insta::assert_snapshot!(format_output(&pilots, OutputFormat::Table).unwrap(), @r#"
+        CLASS  CALLSIGN  PILOT        NATION  GLIDER  DAYS  FLIGHTS
+        club   A3        Jane Doe     CZ      ASW 20  2     2
+        club   AM        John Smith   LT      LS 3    1     1
+        club   CP        Bob Johnson  SI      ASW 20  2     2
+        club   FL        Alice Brown  LT      ASW 20  1     1
+        "#);
+    }
+}
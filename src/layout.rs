@@ -0,0 +1,467 @@
+use crate::date_utils::date_to_igc_filename_prefix;
+use crate::error::Error;
+use crate::parser::DailyResult;
+use crate::url_utils::DailyUrlInfo;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Layout of the downloaded IGC files below the output directory.
+pub const DEFAULT_LAYOUT: &str = "{competition}/{class}/{date}/{igc_prefix}_{callsign}.igc";
+
+/// A value that can be used in a [Layout] template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    Competition,
+    Class,
+    /// Date of the day like "2025-06-19"
+    Date,
+    /// Task name like "task-10"
+    Task,
+    /// Number of the task like "10"
+    TaskNumber,
+    /// Three character date prefix of IGC filenames like "56J"
+    IgcPrefix,
+    Callsign,
+    Pilot,
+    /// Country code of the nationality like "GB"
+    Nation,
+    /// Placement on the day, or the status like "DNF" without one
+    Rank,
+}
+
+impl Placeholder {
+    const ALL: [Placeholder; 10] = [
+        Placeholder::Competition,
+        Placeholder::Class,
+        Placeholder::Date,
+        Placeholder::Task,
+        Placeholder::TaskNumber,
+        Placeholder::IgcPrefix,
+        Placeholder::Callsign,
+        Placeholder::Pilot,
+        Placeholder::Nation,
+        Placeholder::Rank,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Placeholder::Competition => "competition",
+            Placeholder::Class => "class",
+            Placeholder::Date => "date",
+            Placeholder::Task => "task",
+            Placeholder::TaskNumber => "task_number",
+            Placeholder::IgcPrefix => "igc_prefix",
+            Placeholder::Callsign => "callsign",
+            Placeholder::Pilot => "pilot",
+            Placeholder::Nation => "nation",
+            Placeholder::Rank => "rank",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|placeholder| placeholder.name() == name)
+    }
+
+    /// Returns `true` for placeholders that differ between the days of a
+    /// competition.
+    fn is_per_day(self) -> bool {
+        matches!(
+            self,
+            Placeholder::Date
+                | Placeholder::Task
+                | Placeholder::TaskNumber
+                | Placeholder::IgcPrefix
+        )
+    }
+
+    fn value(self, daily_info: &DailyUrlInfo, result: &DailyResult) -> String {
+        match self {
+            Placeholder::Competition => daily_info.competition.clone(),
+            Placeholder::Class => daily_info.class.clone(),
+            Placeholder::Date => daily_info.date.strftime("%Y-%m-%d").to_string(),
+            Placeholder::Task => daily_info.task_name.clone(),
+            Placeholder::TaskNumber => daily_info
+                .task_name
+                .rsplit('-')
+                .next()
+                .filter(|number| number.bytes().all(|b| b.is_ascii_digit()))
+                .unwrap_or_default()
+                .to_string(),
+            Placeholder::IgcPrefix => date_to_igc_filename_prefix(daily_info.date),
+            Placeholder::Callsign => result.callsign.clone(),
+            Placeholder::Pilot => result.pilot.clone(),
+            Placeholder::Nation => result.nationality.clone().unwrap_or_default(),
+            Placeholder::Rank => match (result.rank, &result.status) {
+                (Some(rank), _) => rank.to_string(),
+                (None, Some(status)) => status.clone(),
+                (None, None) => String::new(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// Template for the path of each IGC file below the output directory, like
+/// [DEFAULT_LAYOUT].
+///
+/// Placeholders in braces are replaced with the values of the daily result,
+/// see [Placeholder]. Literal braces can be written as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    template: String,
+    parts: Vec<Part>,
+}
+
+impl Layout {
+    /// Parses and validates a layout template.
+    ///
+    /// The template has to be a relative path, and has to contain the
+    /// callsign and a placeholder for the day, so that files don't
+    /// overwrite each other.
+    pub fn parse(template: &str) -> Result<Self, Error> {
+        let invalid = |message: String| Error::InvalidLayout(format!("{message} in '{template}'"));
+
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let (name, rest) = chars
+                        .as_str()
+                        .split_once('}')
+                        .ok_or_else(|| invalid("unclosed '{'".to_string()))?;
+                    let placeholder = Placeholder::from_name(name).ok_or_else(|| {
+                        let names = Placeholder::ALL.map(|placeholder| placeholder.name());
+                        invalid(format!(
+                            "unknown placeholder '{{{name}}}', expected one of {}",
+                            names.join(", ")
+                        ))
+                    })?;
+                    chars = rest.chars();
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Placeholder(placeholder));
+                }
+                '}' => return Err(invalid("unmatched '}'".to_string())),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        let has = |matches: fn(Placeholder) -> bool| {
+            parts
+                .iter()
+                .any(|part| matches!(part, Part::Placeholder(placeholder) if matches(*placeholder)))
+        };
+        if !has(|placeholder| placeholder == Placeholder::Callsign) {
+            return Err(invalid("missing '{callsign}'".to_string()));
+        }
+        if !has(Placeholder::is_per_day) {
+            return Err(invalid(
+                "missing one of '{date}', '{task}', '{task_number}' or '{igc_prefix}'".to_string(),
+            ));
+        }
+
+        // Values can't add path components, so the template alone decides
+        // whether the files stay inside the output directory
+        let is_relative = Path::new(template)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !is_relative || template.ends_with(['/', '\\']) {
+            return Err(invalid("not a relative path to a file".to_string()));
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            parts,
+        })
+    }
+
+    /// Returns the path of the IGC file of `result`, relative to the output
+    /// directory.
+    ///
+    /// The values are passed through [sanitize], so that they can't change
+    /// the directory structure or produce invalid file names.
+    pub fn render(&self, daily_info: &DailyUrlInfo, result: &DailyResult) -> PathBuf {
+        let path = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Placeholder(placeholder) => sanitize(&placeholder.value(daily_info, result)),
+            })
+            .collect::<String>();
+        PathBuf::from(path)
+    }
+}
+
+/// Names that Windows reserves for devices, with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Latin letters without a decomposition into an ASCII letter and accents.
+const LETTERS: [(char, &str); 16] = [
+    ('Æ', "AE"),
+    ('Ð', "D"),
+    ('Đ', "D"),
+    ('Ł', "L"),
+    ('Ø', "O"),
+    ('Œ', "OE"),
+    ('Þ', "TH"),
+    ('ß', "ss"),
+    ('æ', "ae"),
+    ('ð', "d"),
+    ('đ', "d"),
+    ('ı', "i"),
+    ('ł', "l"),
+    ('ø', "o"),
+    ('œ', "oe"),
+    ('þ', "th"),
+];
+
+/// Makes a value safe to use in a file name on any common filesystem.
+///
+/// Accents are removed first, so that "Jiří Novák" becomes "Jiri_Novak".
+/// Then only ASCII letters, digits, "-", "_" and "." are kept, and every run
+/// of other characters like path separators or spaces becomes a single "_".
+/// Leading and trailing dots are removed, and empty values and reserved
+/// names like "CON" get an "_".
+pub fn sanitize(value: &str) -> String {
+    let ascii = value
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(|c| match LETTERS.iter().find(|(letter, _)| *letter == c) {
+            Some((_, replacement)) => replacement.chars().collect::<Vec<_>>(),
+            None => vec![c],
+        });
+
+    let mut sanitized = String::with_capacity(value.len());
+    for c in ascii {
+        if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+            sanitized.push(c);
+        } else if !sanitized.ends_with('_') {
+            sanitized.push('_');
+        }
+    }
+
+    let sanitized = sanitized.trim_matches('.');
+    let is_reserved = RESERVED_NAMES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(sanitized));
+    if sanitized.is_empty() || is_reserved {
+        format!("{sanitized}_")
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// Keeps track of the IGC file paths of a run, so that different flights
+/// never share a file.
+///
+/// Paths are compared ignoring case, because they would be the same file on
+/// case-insensitive filesystems.
+#[derive(Debug, Default)]
+pub struct UniquePaths {
+    taken: BTreeSet<String>,
+}
+
+impl UniquePaths {
+    /// Claims `path`, or the first free path with a "_2", "_3", ... suffix
+    /// before the extension if it is already claimed or `is_taken` by
+    /// another flight.
+    pub fn claim(&mut self, path: PathBuf, is_taken: impl Fn(&Path) -> bool) -> PathBuf {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy());
+
+        let mut candidate = path.clone();
+        for number in 2.. {
+            let key = candidate.to_string_lossy().to_lowercase();
+            if !self.taken.contains(&key) && !is_taken(&candidate) {
+                self.taken.insert(key);
+                break;
+            }
+
+            let filename = match &extension {
+                Some(extension) => format!("{stem}_{number}.{extension}"),
+                None => format!("{stem}_{number}"),
+            };
+            candidate = path.with_file_name(filename);
+        }
+        candidate
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::parse(DEFAULT_LAYOUT).expect("Invalid default layout")
+    }
+}
+
+impl FromStr for Layout {
+    type Err = Error;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        Self::parse(template)
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_daily_result_table;
+    use crate::url_utils::default_base_url;
+    use jiff::civil::Date;
+
+    fn render(template: &str) -> Vec<PathBuf> {
+        let daily_info = DailyUrlInfo {
+            language: "en_gb".to_string(),
+            competition: "39th-fai-world-gliding-championships-tabor-2025".to_string(),
+            class: "club".to_string(),
+            date: Date::constant(2025, 6, 19),
+            task_name: "task-10".to_string(),
+        };
+        let html = include_str!("../tests/fixtures/day.html");
+        let results = parse_daily_result_table(html, &default_base_url()).unwrap();
+
+        let layout = Layout::parse(template).unwrap();
+        results
+            .iter()
+            .filter(|result| ["AM", "CF"].contains(&result.callsign.as_str()))
+            .map(|result| layout.render(&daily_info, result))
+            .collect()
+    }
+
+    #[test]
+    fn test_default_layout() {
+        insta::assert_debug_snapshot!(render(DEFAULT_LAYOUT), @r#"
+        [
+            "39th-fai-world-gliding-championships-tabor-2025/club/2025-06-19/56J_AM.igc",
+            "39th-fai-world-gliding-championships-tabor-2025/club/2025-06-19/56J_CF.igc",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        insta::assert_debug_snapshot!(
+            render("{class}/{task_number}_{rank}_{nation}_{pilot}_{{{callsign}}}.igc"),
+            @r#"
+            [
+                "club/10_1_LT_John_Smith_{AM}.igc",
+                "club/10_DNF_SI_Nora_Reed_{CF}.igc",
+            ]
+            "#
+        );
+        insta::assert_debug_snapshot!(render("flights/{igc_prefix}_{callsign}.igc"), @r#"
+        [
+            "flights/56J_AM.igc",
+            "flights/56J_CF.igc",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let values = [
+            "AM",
+            "A/B",
+            "a\\b:c",
+            "..",
+            ".hidden.",
+            "K 1",
+            "Jiří Novák",
+            "Łukasz Żółć",
+            "Søren Weiß",
+            "李",
+            "",
+            "con",
+            "Com1",
+        ];
+        insta::assert_debug_snapshot!(values.map(sanitize), @r#"
+        [
+            "AM",
+            "A_B",
+            "a_b_c",
+            "_",
+            "hidden",
+            "K_1",
+            "Jiri_Novak",
+            "Lukasz_Zolc",
+            "Soren_Weiss",
+            "_",
+            "_",
+            "con_",
+            "Com1_",
+        ]
+        "#);
+    }
+
+    #[test]
+    fn test_unique_paths() {
+        let mut paths = UniquePaths::default();
+        let mut claim = |path: &str| {
+            let taken = |path: &Path| path == Path::new("day/KK.igc");
+            paths.claim(PathBuf::from(path), taken)
+        };
+        insta::assert_debug_snapshot!(
+            ["day/AB.igc", "day/ab.igc", "day/Ab.igc", "day/KK.igc", "other/ab.igc"].map(&mut claim),
+            @r#"
+            [
+                "day/AB.igc",
+                "day/ab_2.igc",
+                "day/Ab_3.igc",
+                "day/KK_2.igc",
+                "other/ab.igc",
+            ]
+            "#
+        );
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        let error = |template| Layout::parse(template).unwrap_err().to_string();
+
+        insta::assert_snapshot!(error("{date}/{callsign"), @"Invalid layout: unclosed '{' in '{date}/{callsign'");
+        insta::assert_snapshot!(error("{date}/{callsign}}.igc}"), @"Invalid layout: unmatched '}' in '{date}/{callsign}}.igc}'");
+        insta::assert_snapshot!(error("{date}/{glider}_{callsign}.igc"), @"Invalid layout: unknown placeholder '{glider}', expected one of competition, class, date, task, task_number, igc_prefix, callsign, pilot, nation, rank in '{date}/{glider}_{callsign}.igc'");
+        insta::assert_snapshot!(error("{class}/{callsign}.igc"), @"Invalid layout: missing one of '{date}', '{task}', '{task_number}' or '{igc_prefix}' in '{class}/{callsign}.igc'");
+        insta::assert_snapshot!(error("{date}/{pilot}.igc"), @"Invalid layout: missing '{callsign}' in '{date}/{pilot}.igc'");
+        insta::assert_snapshot!(error("/tmp/{date}/{callsign}.igc"), @"Invalid layout: not a relative path to a file in '/tmp/{date}/{callsign}.igc'");
+        insta::assert_snapshot!(error("../{date}/{callsign}.igc"), @"Invalid layout: not a relative path to a file in '../{date}/{callsign}.igc'");
+        insta::assert_snapshot!(error("{date}/{callsign}/"), @"Invalid layout: not a relative path to a file in '{date}/{callsign}/'");
+    }
+}
@@ -0,0 +1,44 @@
+//! Library for downloading IGC files from [SoaringSpot](https://www.soaringspot.com/)
+//! competition results.
+//!
+//! The `soaringspot-igc-downloader` binary is a thin command-line wrapper
+//! around this crate.
+
+pub mod date_utils;
+pub mod download;
+pub mod downloader;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod igc;
+pub mod igc_filename;
+pub mod inspect;
+pub mod layout;
+pub mod manifest;
+pub mod output;
+pub mod parser;
+pub mod plan;
+pub mod scheduler;
+pub mod task;
+pub mod task_export;
+pub mod url_utils;
+
+pub use date_utils::date_to_igc_filename_prefix;
+pub use download::{
+    DownloadedIgcFile, LocalCopy, SyncOutcome, daily_urls_for_url, download_igc_file,
+    get_daily_results, get_daily_results_if_modified, get_daily_urls_for_competition,
+    get_igc_files, get_task, sync_igc_file,
+};
+pub use downloader::{DownloadOptions, Downloader, Event, FileStatus, Summary};
+pub use error::Error;
+pub use filter::ResultFilter;
+pub use igc::IgcHeader;
+pub use igc_filename::IgcFilename;
+pub use layout::Layout;
+pub use parser::{
+    DailyResult, IgcFile, Penalty, parse_daily_result_table, parse_daily_results, parse_igc_files,
+    parse_task,
+};
+pub use scheduler::Scheduler;
+pub use task::Task;
+pub use url_utils::{DailyUrlInfo, UrlInfo, extract_url_info};
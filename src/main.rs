@@ -1,23 +1,37 @@
-mod date_utils;
-mod parser;
-mod url_utils;
-
-use crate::url_utils::DailyUrlInfo;
-use clap::Parser;
-use date_utils::date_to_igc_filename_prefix;
-use indicatif::{ProgressBar, ProgressStyle};
-use parser::parse_igc_files;
+use clap::{Parser, Subcommand};
+use futures::{StreamExt, stream};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use jiff::{SignedDuration, Zoned};
+use soaringspot_igc_downloader::downloader::DEFAULT_CONCURRENCY;
+use soaringspot_igc_downloader::export::ExportFormat;
+use soaringspot_igc_downloader::layout::DEFAULT_LAYOUT;
+use soaringspot_igc_downloader::output::{OutputFormat, format_output};
+use soaringspot_igc_downloader::plan::format_plan;
+use soaringspot_igc_downloader::scheduler::{DEFAULT_REQUESTS_PER_SECOND, RetryPolicy};
+use soaringspot_igc_downloader::url_utils::DEFAULT_BASE_URL;
+use soaringspot_igc_downloader::{
+    DailyResult, DailyUrlInfo, DownloadOptions, Downloader, Error, Event, FileStatus, Layout,
+    ResultFilter, Scheduler, Summary, daily_urls_for_url, get_daily_results, inspect,
+};
+use std::fmt;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
-use tempfile::NamedTempFile;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tokio::time::{Duration, sleep};
+use std::sync::Mutex;
+use std::time::Duration;
 use url::Url;
-use url_utils::{UrlInfo, extract_url_info};
 
 #[derive(Parser)]
-#[command(about, version)]
+#[command(
+    about,
+    version,
+    subcommand_negates_reqs = true,
+    override_usage = "soaringspot-igc-downloader [OPTIONS] <URL>\n       \
+                      soaringspot-igc-downloader <COMMAND> [OPTIONS] <URL>"
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// SoaringSpot URL to download from
     ///
     /// Examples:
@@ -27,242 +41,511 @@ struct Args {
     /// - https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/standard
     ///
     /// - https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/task-4-on-2025-06-12/daily
-    url: Url,
+    #[arg(required = true)]
+    url: Option<Url>,
 
     /// Output directory for IGC files (defaults to current directory)
     #[arg(short, long)]
     output: Option<PathBuf>,
 
-    /// Include practice days in the downloads
-    #[arg(long)]
+    /// Include practice days in the downloads and the list of pilots
+    #[arg(long, global = true)]
     include_practice: bool,
+
+    /// Maximum number of pages and IGC files to download in parallel
+    #[arg(short = 'j', long, default_value_t = DEFAULT_CONCURRENCY, global = true)]
+    concurrency: NonZeroUsize,
+
+    /// Maximum number of HTTP requests per second sent to SoaringSpot
+    /// (0 disables the limit)
+    #[arg(
+        long,
+        default_value_t = DEFAULT_REQUESTS_PER_SECOND,
+        value_parser = parse_requests_per_second,
+        global = true
+    )]
+    requests_per_second: f64,
+
+    /// Maximum number of retries for rate limited or failed requests
+    #[arg(long, default_value_t = RetryPolicy::default().max_retries, global = true)]
+    max_retries: u32,
+
+    /// Delay before the first retry in milliseconds, doubled after each
+    /// further retry
+    #[arg(
+        long,
+        default_value_t = RetryPolicy::default().initial_delay.as_millis() as u64,
+        global = true
+    )]
+    retry_delay_ms: u64,
+
+    /// Export the daily results next to the IGC files, plus a combined file
+    /// per competition
+    #[arg(long, value_name = "FORMAT")]
+    export: Option<ExportFormat>,
+
+    /// Warn when the date or competition ID in a downloaded IGC file does
+    /// not match the SoaringSpot results
+    #[arg(long)]
+    check_headers: bool,
+
+    /// Write the task of each day as XCSoar (task.tsk) and SeeYou (task.cup)
+    /// files next to the IGC files
+    #[arg(long)]
+    tasks: bool,
+
+    /// Only load days and IGC files that changed since the last run, based
+    /// on the manifest, and replace updated files
+    #[arg(long)]
+    sync: bool,
+
+    /// Keep running until interrupted, checking for new days and flights
+    /// once per interval (implies --sync)
+    #[arg(long)]
+    watch: bool,
+
+    /// Time between two checks in watch mode, e.g. "10m" or "1h"
+    #[arg(long, default_value = "10m", value_parser = parse_interval)]
+    interval: Duration,
+
+    /// Base URL of the site to load the pages and IGC files from, e.g. an
+    /// archived mirror or a local test server
+    #[arg(long, default_value = DEFAULT_BASE_URL, global = true)]
+    base_url: Url,
+
+    /// Only include these callsigns, e.g. "KK,LS"
+    #[arg(long, value_delimiter = ',')]
+    callsign: Vec<String>,
+
+    /// Only include pilots of these nationalities, e.g. "GB"
+    #[arg(long, value_delimiter = ',')]
+    nation: Vec<String>,
+
+    /// Only include gliders whose type contains this text, e.g. "ASG 29"
+    /// (can be repeated)
+    #[arg(long)]
+    glider: Vec<String>,
+
+    /// Only include pilots whose name contains this text (can be repeated)
+    #[arg(long)]
+    pilot: Vec<String>,
+
+    /// Path of each IGC file below the output directory, with the
+    /// placeholders {competition}, {class}, {date}, {task}, {task_number},
+    /// {igc_prefix}, {callsign}, {pilot}, {nation} and {rank}
+    #[arg(long, default_value = DEFAULT_LAYOUT)]
+    layout: Layout,
+
+    /// Name the IGC files like 2025-06-19-LXV-ABC-01.igc, following the
+    /// long IGC filename convention with the logger from the A record,
+    /// instead of the file name of the layout
+    #[arg(long)]
+    long_filenames: bool,
+
+    /// Only print the classes, days, pilots and paths of the IGC files that
+    /// would be downloaded, as a table or as JSON, without writing anything
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "table",
+        conflicts_with = "watch"
+    )]
+    dry_run: Option<OutputFormat>,
+}
+
+/// Commands that print what a competition contains instead of downloading.
+#[derive(Subcommand)]
+enum Command {
+    /// List the classes of a competition with their number of days
+    Classes(InspectArgs),
+    /// List the days of a competition or class, including practice days
+    Days(InspectArgs),
+    /// List the contestants of a competition or class from the daily
+    /// results
+    Pilots(InspectArgs),
+}
+
+#[derive(clap::Args)]
+struct InspectArgs {
+    /// SoaringSpot URL of a competition, class or day
+    url: Url,
+
+    /// Print the list as a table or as JSON
+    #[arg(long, default_value = "table")]
+    format: OutputFormat,
+}
+
+impl Args {
+    fn download_options(&self) -> DownloadOptions {
+        DownloadOptions {
+            output_dir: self.output.clone().unwrap_or_else(|| PathBuf::from(".")),
+            include_practice: self.include_practice,
+            concurrency: self.concurrency,
+            filter: ResultFilter {
+                callsigns: self.callsign.clone(),
+                nations: self.nation.clone(),
+                gliders: self.glider.clone(),
+                pilots: self.pilot.clone(),
+            },
+            layout: self.layout.clone(),
+            export: self.export,
+            tasks: self.tasks,
+            check_headers: self.check_headers,
+            sync: self.sync || self.watch,
+            long_filenames: self.long_filenames,
+            dry_run: self.dry_run.is_some(),
+        }
+    }
+
+    /// Prints a status message, on stderr if stdout is used for JSON.
+    fn status(&self, message: impl fmt::Display) {
+        let format = match &self.command {
+            Some(
+                Command::Classes(inspect_args)
+                | Command::Days(inspect_args)
+                | Command::Pilots(inspect_args),
+            ) => Some(inspect_args.format),
+            None => self.dry_run,
+        };
+        match format {
+            Some(OutputFormat::Json) => eprintln!("{message}"),
+            _ => println!("{message}"),
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
-    let client = reqwest::Client::new();
-    let daily_urls = daily_urls_for_url(&client, &args.url, args.include_practice).await?;
-
-    let progress_bar = ProgressBar::new(daily_urls.len() as u64);
-    progress_bar.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta}) {msg}")
-            .unwrap()
-            .progress_chars("#>-")
-    );
-
-    let mut igc_files = vec![];
-    for daily_url in daily_urls {
-        let url = daily_url.to_daily_url();
-        progress_bar.set_message(format!(
-            "Loading results page for {} class on {}",
-            daily_url.class, daily_url.date
-        ));
+    let retry_policy = RetryPolicy {
+        max_retries: args.max_retries,
+        initial_delay: Duration::from_millis(args.retry_delay_ms),
+        ..RetryPolicy::default()
+    };
+    let scheduler = Scheduler::new(reqwest::Client::new())
+        .with_requests_per_second(args.requests_per_second)
+        .with_retry_policy(retry_policy)
+        .with_base_url(args.base_url.clone());
+
+    if let Some(command) = &args.command {
+        return inspect(&args, command, &scheduler).await;
+    }
 
-        let response = client.get(url).send().await?;
-        if !response.status().is_success() {
-            progress_bar.println(format!(
-                "Failed to download HTML: HTTP {}",
-                response.status()
-            ));
-            return Err(format!("HTTP error: {}", response.status()).into());
-        }
+    let url = args
+        .url
+        .as_ref()
+        .expect("The URL is required without a command");
+    let progress = Progress::new(&args);
+    let downloader = Downloader::new(&scheduler, args.download_options())
+        .with_events(|event| progress.show(event));
+    if args.watch {
+        return watch(&args, &downloader, &progress, url).await;
+    }
 
-        let html = response.text().await?;
+    let summary = progress.run(&downloader, url).await?;
+    if let Some(format) = args.dry_run {
+        println!("{}", format_plan(&summary.plan, format)?);
+    }
+    report_failed_days(&summary.failed_days, &args)
+}
 
-        // Parse HTML and extract IGC file information
-        let daily_igc_files = parse_igc_files(&html)?;
-        progress_bar.println(format!(
-            "✓ Processed: {} class on {}",
-            daily_url.class, daily_url.date
-        ));
-        progress_bar.inc(1);
+/// Shows the progress of download runs with progress bars.
+struct Progress<'a> {
+    args: &'a Args,
+    multi_progress: MultiProgress,
+    /// Bar for the daily results pages, then for the IGC files
+    progress_bar: Mutex<ProgressBar>,
+}
 
-        if !daily_igc_files.is_empty() {
-            igc_files.push((daily_url, daily_igc_files));
+impl<'a> Progress<'a> {
+    fn new(args: &'a Args) -> Self {
+        Self {
+            args,
+            multi_progress: MultiProgress::new(),
+            progress_bar: Mutex::new(ProgressBar::hidden()),
         }
     }
 
-    progress_bar.finish_with_message("Download complete!");
+    /// Runs `downloader` once, with status messages before and after.
+    async fn run(&self, downloader: &Downloader<'_>, url: &Url) -> Result<Summary, Error> {
+        self.args.status(format!("Loading results from: {url}"));
+        let result = downloader.run(url).await;
 
-    if igc_files.is_empty() {
-        println!("No IGC files found to download");
-        return Ok(());
+        let progress_bar = self.progress_bar.lock().unwrap();
+        match self.args.dry_run {
+            Some(_) => progress_bar.finish_and_clear(),
+            None => progress_bar.finish_with_message("Download complete!"),
+        }
+        result
     }
 
-    // Determine output directory and create directory structure
-    let output_dir = args.output.unwrap_or_else(|| PathBuf::from("."));
-
-    let total_files = igc_files
-        .iter()
-        .map(|(_, files)| files.len())
-        .sum::<usize>();
-
-    println!("Found {} IGC files", total_files);
-
-    // Create progress bar
-    let progress_bar = ProgressBar::new(total_files as u64);
-    progress_bar.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta}) {msg}")
-            .unwrap()
-            .progress_chars("#>-")
-    );
-
-    for (daily_info, igc_files) in igc_files {
-        // Create directory structure: {output}/{competition}/{class}/{date}/
-        let date_str = daily_info.date.strftime("%Y-%m-%d").to_string();
-        let target_dir = output_dir
-            .join(&daily_info.competition)
-            .join(&daily_info.class)
-            .join(date_str);
-
-        fs::create_dir_all(&target_dir).await?;
-        progress_bar.println(format!("Downloading to: {}", target_dir.display()));
-
-        // Generate date prefix for filenames
-        let date_prefix = date_to_igc_filename_prefix(daily_info.date);
-
-        // Download each IGC file
-        for igc_file in igc_files {
-            let filename = format!("{}_{}.igc", date_prefix, igc_file.callsign);
-            let file_path = target_dir.join(&filename);
-
-            progress_bar.set_message(format!("Downloading {}", filename));
-
-            // Skip if file already exists
-            if file_path.exists() {
-                progress_bar.println(format!("⏭ Skipping existing file: {}", filename));
+    /// Starts a new progress bar with `len` steps.
+    fn start(&self, len: usize) {
+        let progress_bar = self.multi_progress.add(ProgressBar::new(len as u64));
+        progress_bar.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta}) {msg}")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        *self.progress_bar.lock().unwrap() = progress_bar;
+    }
+
+    fn show(&self, event: Event<'_>) {
+        let progress_bar = self.progress_bar.lock().unwrap().clone();
+        match event {
+            Event::InvalidManifest { path, error } => {
+                self.multi_progress
+                    .println(format!("⚠ Ignoring invalid {}: {}", path.display(), error))
+                    .unwrap();
+            }
+            Event::LoadingDays(len) => self.start(len),
+            Event::LoadingDay(day) => progress_bar.set_message(format!(
+                "Loading results page for {} class on {}",
+                day.class, day.date
+            )),
+            Event::DayLoaded { day, changed } => {
+                match changed {
+                    true => progress_bar
+                        .println(format!("✓ Processed: {} class on {}", day.class, day.date)),
+                    false => progress_bar
+                        .println(format!("⏭ Unchanged: {} class on {}", day.class, day.date)),
+                }
                 progress_bar.inc(1);
-                continue;
             }
-
-            // Download to temporary file first
-            match download_igc_file(&client, &igc_file.download_url, &file_path, &progress_bar)
-                .await
-            {
-                Ok(_) => {
-                    progress_bar.println(format!("✓ Downloaded: {}", filename));
+            Event::DayFailed { day, error } => {
+                progress_bar.println(format!(
+                    "✗ Failed to load results page for {} class on {}: {}",
+                    day.class, day.date, error
+                ));
+                progress_bar.inc(1);
+            }
+            Event::FilesFound(0) => {
+                progress_bar.finish_with_message("Download complete!");
+                if self.args.export.is_none() && !self.args.tasks && self.args.dry_run.is_none() {
+                    println!("No IGC files found to download");
                 }
-                Err(e) => {
-                    progress_bar.println(format!("✗ Failed to download {}: {}", filename, e));
+                self.start(0);
+            }
+            Event::FilesFound(len) => {
+                progress_bar.finish_with_message("Download complete!");
+                self.args.status(format!("Found {len} IGC files"));
+                self.start(len);
+            }
+            Event::TaskExported(path) => {
+                progress_bar.println(format!("✓ Exported: {}", path.display()));
+            }
+            Event::TaskFailed { day, error } => progress_bar.println(format!(
+                "✗ Failed to export task for {} class on {}: {}",
+                day.class, day.date, error
+            )),
+            Event::PathTaken {
+                day,
+                callsign,
+                path,
+                unique_path,
+            } => progress_bar.println(format!(
+                "⚠ {} is already used by another flight, saving {} of {} class on {} as {}",
+                path.display(),
+                callsign,
+                day.class,
+                day.date,
+                unique_path.display()
+            )),
+            Event::CreatedDirectory(path) => {
+                progress_bar.println(format!("Downloading to: {}", path.display()));
+            }
+            Event::Downloading { filename, bytes } => {
+                progress_bar.set_message(format!("Downloading {filename}"));
+                let bytes = self.multi_progress.add(bytes.clone());
+                bytes.set_style(
+                    ProgressStyle::default_bar()
+                        .template(
+                            "  {msg} [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec})",
+                        )
+                        .unwrap()
+                        .progress_chars("#>-"),
+                );
+                bytes.set_message(filename.to_string());
+            }
+            Event::DownloadEnded { bytes, .. } => {
+                bytes.finish_and_clear();
+                self.multi_progress.remove(bytes);
+            }
+            Event::FileDone { filename, status } => {
+                match status {
+                    FileStatus::Skipped => {
+                        progress_bar.println(format!("⏭ Skipping existing file: {filename}"))
+                    }
+                    FileStatus::Unchanged => {
+                        progress_bar.println(format!("⏭ Unchanged: {filename}"))
+                    }
+                    FileStatus::Downloaded => {
+                        progress_bar.println(format!("✓ Downloaded: {filename}"))
+                    }
+                    FileStatus::Updated => progress_bar.println(format!("✓ Updated: {filename}")),
                 }
+                progress_bar.inc(1);
             }
-
-            progress_bar.inc(1);
+            Event::FileFailed { filename, error } => {
+                progress_bar.println(format!("✗ Failed to download {filename}: {error}"));
+                progress_bar.inc(1);
+            }
+            Event::ReadFailed { filename, error } => {
+                progress_bar.println(format!("✗ Failed to read {filename}: {error}"));
+            }
+            Event::HeaderMismatch { filename, mismatch } => {
+                progress_bar.println(format!("⚠ {filename}: {mismatch}"));
+            }
+            Event::MissingLogger { filename } => progress_bar.println(format!(
+                "⚠ {filename}: no logger in the A record, keeping the name"
+            )),
+            Event::ManifestWritten(path) => println!("✓ Manifest: {}", path.display()),
+            Event::InvalidResults { path, error } => {
+                println!("⚠ Skipping invalid {}: {}", path.display(), error);
+            }
+            Event::Exported(path) => println!("✓ Exported: {}", path.display()),
         }
     }
-
-    progress_bar.finish_with_message("Download complete!");
-
-    Ok(())
 }
 
-async fn daily_urls_for_url(
-    client: &reqwest::Client,
-    url: &Url,
-    include_practice: bool,
-) -> Result<Vec<DailyUrlInfo>, Box<dyn std::error::Error>> {
-    let url_info = extract_url_info(url)?;
-    Ok(match url_info {
-        UrlInfo::Daily(daily) => vec![daily],
-        UrlInfo::Class { competition, class } => {
-            get_daily_urls_for_competition(client, &competition, include_practice)
-                .await?
-                .into_iter()
-                .filter(|info| info.class == class)
-                .collect()
+/// Prints the classes, days or pilots of a competition.
+async fn inspect(
+    args: &Args,
+    command: &Command,
+    scheduler: &Scheduler,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (Command::Classes(inspect_args)
+    | Command::Days(inspect_args)
+    | Command::Pilots(inspect_args)) = command;
+    let format = inspect_args.format;
+
+    // Classes and days are always listed with their practice days, which
+    // are flagged instead
+    let include_practice = args.include_practice || !matches!(command, Command::Pilots(_));
+    args.status(format!("Loading results from: {}", inspect_args.url));
+    let daily_urls = daily_urls_for_url(scheduler, &inspect_args.url, include_practice).await?;
+
+    let mut failed_days = vec![];
+    let output = match command {
+        Command::Classes(_) => format_output(&inspect::classes(&daily_urls), format)?,
+        Command::Days(_) => {
+            format_output(&inspect::days(&daily_urls, scheduler.base_url()), format)?
         }
-        UrlInfo::Competition { competition } => {
-            get_daily_urls_for_competition(client, &competition, include_practice).await?
+        Command::Pilots(_) => {
+            args.status(format!("Loading {} daily results pages", daily_urls.len()));
+            let daily_results = load_daily_results(scheduler, daily_urls, args.concurrency).await;
+
+            let mut loaded_days = vec![];
+            for (daily_url, result) in daily_results {
+                match result {
+                    Ok(results) => loaded_days.push((daily_url, results)),
+                    Err(e) => failed_days.push((daily_url, e)),
+                }
+            }
+            format_output(&inspect::pilots(&loaded_days), format)?
         }
-    })
-}
-
-async fn get_daily_urls_for_competition(
-    client: &reqwest::Client,
-    competition: &str,
-    include_practice: bool,
-) -> Result<Vec<DailyUrlInfo>, Box<dyn std::error::Error>> {
-    let url = format!("https://www.soaringspot.com/en_gb/{competition}/results");
-    println!("Loading results page from: {}", url);
+    };
 
-    let response = client.get(&url).send().await?;
-    if !response.status().is_success() {
-        return Err(format!("HTTP error {}: {}", response.status(), url).into());
-    }
+    println!("{output}");
+    report_failed_days(&failed_days, args)
+}
 
-    let html = response.text().await?;
-    Ok(parser::parse_daily_results(&html)?
-        .into_iter()
-        .filter(|info| {
-            // Filter out practice days if not requested
-            include_practice || !info.is_practice_day()
+/// Loads the daily results pages concurrently, keeping them in order.
+async fn load_daily_results(
+    scheduler: &Scheduler,
+    daily_urls: Vec<DailyUrlInfo>,
+    concurrency: NonZeroUsize,
+) -> Vec<(DailyUrlInfo, Result<Vec<DailyResult>, Error>)> {
+    stream::iter(daily_urls)
+        .map(|daily_url| async move {
+            let result = get_daily_results(scheduler, &daily_url).await;
+            (daily_url, result)
         })
-        .collect())
+        .buffered(concurrency.get())
+        .collect()
+        .await
 }
 
-async fn download_igc_file(
-    client: &reqwest::Client,
-    url: &str,
-    final_path: &PathBuf,
-    progress_bar: &ProgressBar,
+/// Downloads new days and flights once per interval, until interrupted.
+async fn watch(
+    args: &Args,
+    downloader: &Downloader<'_>,
+    progress: &Progress<'_>,
+    url: &Url,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Retry parameters
-    const MAX_RETRIES: u32 = 5;
-    const INITIAL_DELAY_MS: u64 = 1000; // 1 second
-    const BACKOFF_FACTOR: u64 = 2; // Exponential backoff multiplier
-
-    let mut retry_count = 0;
-    let mut delay_ms = INITIAL_DELAY_MS;
-
-    loop {
-        let response = client.get(url).send().await?;
-        if !response.status().is_success() {
-            return Err(format!("HTTP error {}: {}", response.status(), url).into());
+    for cycle in 1.. {
+        tokio::select! {
+            result = progress.run(downloader, url) => match result {
+                Ok(summary) => {
+                    // Failed days are retried in the next cycle
+                    let _ = report_failed_days(&summary.failed_days, args);
+                    println!("Cycle {cycle}: {summary}");
+                }
+                Err(e) => println!("✗ Cycle {cycle} failed: {e}"),
+            },
+            _ = tokio::signal::ctrl_c() => return Ok(()),
         }
 
-        let content = response.bytes().await?;
-
-        // check for "Too Many Requests"
-        if std::str::from_utf8(&content)
-            .unwrap_or("")
-            .contains("<h1>Too Many Requests</h1>")
-        {
-            if retry_count >= MAX_RETRIES {
-                return Err(format!(
-                    "Exceeded maximum retries ({}) for Too Many Requests: {}",
-                    MAX_RETRIES, url
-                )
-                .into());
-            }
-
-            retry_count += 1;
-            progress_bar.println(format!(
-                "Received 'Too Many Requests' response in content. Retrying in {} ms (attempt {}/{})",
-                delay_ms, retry_count, MAX_RETRIES
-            ));
-
-            sleep(Duration::from_millis(delay_ms)).await;
-            delay_ms *= BACKOFF_FACTOR; // Exponential backoff
-            continue;
+        let next_check = Zoned::now().checked_add(args.interval)?;
+        println!("Next check at {}", next_check.strftime("%H:%M:%S"));
+        tokio::select! {
+            _ = tokio::time::sleep(args.interval) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
         }
+    }
 
-        // Create a temporary file
-        let temp_file = NamedTempFile::new()?;
-        let temp_path = temp_file.path();
+    Ok(())
+}
 
-        // Write content to temporary file
-        let mut file = fs::File::create(temp_path).await?;
-        file.write_all(&content).await?;
-        file.shutdown().await?;
-        drop(file);
+/// Parses intervals like "10m", "1h 30m" or "PT10M".
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    let duration = value
+        .parse::<SignedDuration>()
+        .map_err(|error| error.to_string())?;
+    match Duration::try_from(duration) {
+        Ok(duration) if !duration.is_zero() => Ok(duration),
+        _ => Err("interval must be positive".to_string()),
+    }
+}
 
-        // Atomically move temp file to final location
-        fs::rename(temp_path, final_path).await?;
+/// Parses a request budget, which has to be a positive number or 0.
+fn parse_requests_per_second(value: &str) -> Result<f64, String> {
+    let requests_per_second = value.parse::<f64>().map_err(|error| error.to_string())?;
+    if !requests_per_second.is_finite() || requests_per_second < 0.0 {
+        return Err("must be a positive number, or 0 for no limit".to_string());
+    }
+    if requests_per_second > 0.0 && Duration::try_from_secs_f64(1.0 / requests_per_second).is_err()
+    {
+        return Err("is too small".to_string());
+    }
+    Ok(requests_per_second)
+}
 
+fn report_failed_days(
+    failed_days: &[(DailyUrlInfo, Error)],
+    args: &Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if failed_days.is_empty() {
         return Ok(());
     }
+
+    args.status(format!(
+        "Failed to load {} daily results pages:",
+        failed_days.len()
+    ));
+    for (daily_url, e) in failed_days {
+        args.status(format!(
+            "✗ {} class on {} ({}): {}",
+            daily_url.class,
+            daily_url.date,
+            daily_url.to_daily_url(&args.base_url),
+            e
+        ));
+    }
+
+    Err(format!("Failed to load {} daily results pages", failed_days.len()).into())
 }
@@ -0,0 +1,465 @@
+use crate::error::Error;
+use crate::scheduler::CacheValidators;
+use crate::url_utils::DailyUrlInfo;
+use jiff::Timestamp;
+use jiff::civil::Date;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+
+/// Name of the manifest file in each competition directory.
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// Record of all files downloaded for a competition.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredManifest")]
+pub struct Manifest {
+    pub competition: String,
+    /// Daily results pages that have been processed
+    pub days: Vec<ManifestDay>,
+    files: Vec<ManifestEntry>,
+    /// Indices into `files` by normalised path, see [normalize_path]
+    #[serde(skip)]
+    paths: HashMap<String, usize>,
+    /// Indices into `files` by flight, see [ManifestEntry::flight_key]
+    #[serde(skip)]
+    flights: HashMap<FlightKey, usize>,
+}
+
+/// Manifest as stored in the file, without the indices.
+#[derive(Deserialize)]
+struct StoredManifest {
+    competition: String,
+    #[serde(default)]
+    days: Vec<ManifestDay>,
+    files: Vec<ManifestEntry>,
+}
+
+impl From<StoredManifest> for Manifest {
+    fn from(stored: StoredManifest) -> Self {
+        let mut manifest = Self {
+            competition: stored.competition,
+            days: stored.days,
+            files: stored.files,
+            ..Self::default()
+        };
+        manifest.reindex();
+        manifest
+    }
+}
+
+/// Class, date, task name and callsign of a flight.
+type FlightKey = (String, Date, String, String);
+
+/// A processed daily results page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestDay {
+    pub class: String,
+    pub date: Date,
+    pub task_name: String,
+    /// Language the daily results page was loaded in, so that a later sync
+    /// loads the same page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Cache validators of the daily results page
+    #[serde(flatten)]
+    pub validators: CacheValidators,
+}
+
+impl ManifestDay {
+    fn matches(&self, daily_info: &DailyUrlInfo) -> bool {
+        self.class == daily_info.class
+            && self.date == daily_info.date
+            && self.task_name == daily_info.task_name
+    }
+}
+
+/// A single downloaded IGC file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub class: String,
+    pub date: Date,
+    pub task_name: String,
+    pub callsign: String,
+    /// Language of the daily results page the file was found on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// URL the file was downloaded from
+    pub source_url: String,
+    /// Location of the file, relative to the competition directory
+    pub path: PathBuf,
+    /// File size in bytes
+    pub size: u64,
+    /// Hex encoded SHA-256 hash of the file content
+    pub sha256: String,
+    pub downloaded_at: Timestamp,
+    /// Cache validators of the response the file was downloaded from
+    #[serde(flatten)]
+    pub validators: CacheValidators,
+}
+
+impl ManifestEntry {
+    pub fn new(
+        daily_info: &DailyUrlInfo,
+        callsign: &str,
+        source_url: &str,
+        path: PathBuf,
+        size: u64,
+        sha256: String,
+        downloaded_at: Timestamp,
+    ) -> Self {
+        Self {
+            class: daily_info.class.clone(),
+            date: daily_info.date,
+            task_name: daily_info.task_name.clone(),
+            callsign: callsign.to_string(),
+            language: Some(daily_info.language.clone()),
+            source_url: source_url.to_string(),
+            path,
+            size,
+            sha256,
+            downloaded_at,
+            validators: CacheValidators::default(),
+        }
+    }
+
+    /// Returns `true` if the file is the flight of `callsign` on the day of
+    /// `daily_info`.
+    pub fn is_flight(&self, daily_info: &DailyUrlInfo, callsign: &str) -> bool {
+        self.class == daily_info.class
+            && self.date == daily_info.date
+            && self.task_name == daily_info.task_name
+            && self.callsign == callsign
+    }
+
+    fn flight_key(&self) -> FlightKey {
+        (
+            self.class.clone(),
+            self.date,
+            self.task_name.clone(),
+            self.callsign.clone(),
+        )
+    }
+}
+
+impl Manifest {
+    pub fn new(competition: &str) -> Self {
+        Self {
+            competition: competition.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Returns all downloaded files.
+    pub fn files(&self) -> &[ManifestEntry] {
+        &self.files
+    }
+
+    /// Loads the manifest from `path`, returning `None` if it does not exist.
+    pub async fn load(path: &Path) -> Result<Option<Self>, Error> {
+        match fs::read(path).await {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Writes the manifest to `path`, replacing any existing file atomically.
+    pub async fn write(&self, path: &Path) -> Result<(), Error> {
+        let content = serde_json::to_vec_pretty(self)?;
+
+        let target_dir = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let temp_file = tempfile::Builder::new()
+            .prefix(".")
+            .suffix(".json.part")
+            .tempfile_in(target_dir)?;
+        fs::write(temp_file.path(), content).await?;
+        temp_file.persist(path).map_err(|error| error.error)?;
+
+        Ok(())
+    }
+
+    /// Returns the entry for the file at `path`.
+    ///
+    /// Like [UniquePaths](crate::layout::UniquePaths), paths are compared
+    /// ignoring case, because they would be the same file on
+    /// case-insensitive filesystems.
+    pub fn get(&self, path: &Path) -> Option<&ManifestEntry> {
+        let index = *self.paths.get(&normalize_path(path))?;
+        Some(&self.files[index])
+    }
+
+    /// Adds `entry`, replacing any previous entry for the same path.
+    pub fn insert(&mut self, entry: ManifestEntry) {
+        match self.paths.get(&normalize_path(&entry.path)) {
+            Some(&index) => {
+                // The flight index only changes if the path now holds
+                // another flight, which is rare
+                let same_flight = self.files[index].flight_key() == entry.flight_key();
+                self.files[index] = entry;
+                if !same_flight {
+                    self.reindex();
+                }
+            }
+            None => {
+                let index = self.files.len();
+                self.paths.insert(normalize_path(&entry.path), index);
+                self.flights.entry(entry.flight_key()).or_insert(index);
+                self.files.push(entry);
+            }
+        }
+    }
+
+    /// Returns the entry for the flight of `callsign` on the day of
+    /// `daily_info`, wherever the file was stored.
+    pub fn flight(&self, daily_info: &DailyUrlInfo, callsign: &str) -> Option<&ManifestEntry> {
+        let key = (
+            daily_info.class.clone(),
+            daily_info.date,
+            daily_info.task_name.clone(),
+            callsign.to_string(),
+        );
+        let index = *self.flights.get(&key)?;
+        Some(&self.files[index])
+    }
+
+    /// Returns the record of a previously processed daily results page.
+    pub fn day(&self, daily_info: &DailyUrlInfo) -> Option<&ManifestDay> {
+        self.days.iter().find(|day| day.matches(daily_info))
+    }
+
+    /// Records that the daily results page of `daily_info` was processed.
+    pub fn insert_day(&mut self, daily_info: &DailyUrlInfo, validators: CacheValidators) {
+        let day = ManifestDay {
+            class: daily_info.class.clone(),
+            date: daily_info.date,
+            task_name: daily_info.task_name.clone(),
+            language: Some(daily_info.language.clone()),
+            validators,
+        };
+        match self
+            .days
+            .iter_mut()
+            .find(|existing| existing.matches(daily_info))
+        {
+            Some(existing) => *existing = day,
+            None => self.days.push(day),
+        }
+    }
+
+    /// Sorts the days by class and date, and the entries by class, date and
+    /// callsign.
+    pub fn sort(&mut self) {
+        self.days
+            .sort_by(|a, b| (&a.class, a.date).cmp(&(&b.class, b.date)));
+        self.files
+            .sort_by(|a, b| (&a.class, a.date, &a.callsign).cmp(&(&b.class, b.date, &b.callsign)));
+        self.reindex();
+    }
+
+    /// Rebuilds the indices of `files`, where the first entry wins if
+    /// several have the same path or flight.
+    fn reindex(&mut self) {
+        self.paths.clear();
+        self.flights.clear();
+        for (index, entry) in self.files.iter().enumerate() {
+            self.paths
+                .entry(normalize_path(&entry.path))
+                .or_insert(index);
+            self.flights.entry(entry.flight_key()).or_insert(index);
+        }
+    }
+}
+
+/// Returns the key that paths are compared by in the manifest.
+fn normalize_path(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+/// Incrementally computes the size and SHA-256 hash of a file.
+#[derive(Debug, Default)]
+pub struct FileHasher {
+    size: u64,
+    hasher: Sha256,
+}
+
+impl FileHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.size += chunk.len() as u64;
+        self.hasher.update(chunk);
+    }
+
+    /// Returns the size in bytes and the hex encoded hash.
+    pub fn finish(self) -> (u64, String) {
+        let hash = self.hasher.finalize();
+        let hex = hash.iter().map(|byte| format!("{byte:02x}")).collect();
+        (self.size, hex)
+    }
+}
+
+/// Computes the size and hex encoded SHA-256 hash of the file at `path`.
+pub async fn hash_file(path: &Path) -> Result<(u64, String), Error> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = FileHasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(callsign: &str, sha256: &str) -> ManifestEntry {
+        let daily_info = DailyUrlInfo {
+            language: "en_gb".to_string(),
+            competition: "39th-fai-world-gliding-championships-tabor-2025".to_string(),
+            class: "club".to_string(),
+            date: Date::constant(2025, 6, 19),
+            task_name: "task-10".to_string(),
+        };
+        ManifestEntry::new(
+            &daily_info,
+            callsign,
+            "https://www.soaringspot.com/en_gb/download-contest-flight/5039-10179576303?dl=1",
+            PathBuf::from(format!("club/2025-06-19/56J_{callsign}.igc")),
+            42,
+            sha256.to_string(),
+            "2025-06-19T18:30:00Z".parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_file_hasher() {
+        let mut hasher = FileHasher::new();
+        hasher.update(b"AXXX");
+        hasher.update(b"001\r\n");
+        insta::assert_debug_snapshot!(hasher.finish(), @r#"
+        (
+            9,
+            "d737fbc0dd9861a26601d1bcb71143dd6705609e1d2df66fb8e2bc13017cf24d",
+        )
+        "#);
+    }
+
+    #[test]
+    fn test_manifest_insert() {
+        let mut manifest = Manifest::new("39th-fai-world-gliding-championships-tabor-2025");
+        manifest.insert(entry("AM", "aa"));
+        manifest.insert(entry("A3", "bb"));
+        manifest.insert(entry("AM", "cc"));
+
+        assert_eq!(manifest.files().len(), 2);
+        let path = Path::new("club/2025-06-19/56J_AM.igc");
+        assert_eq!(manifest.get(path).unwrap().sha256, "cc");
+        let path = Path::new("club/2025-06-19/56J_am.igc");
+        assert_eq!(manifest.get(path).unwrap().callsign, "AM");
+
+        let daily_info = DailyUrlInfo {
+            language: "en_gb".to_string(),
+            competition: manifest.competition.clone(),
+            class: "club".to_string(),
+            date: Date::constant(2025, 6, 19),
+            task_name: "task-10".to_string(),
+        };
+        assert_eq!(manifest.flight(&daily_info, "A3").unwrap().sha256, "bb");
+        assert_eq!(manifest.flight(&daily_info, "CX"), None);
+
+        // The lookups still work after sorting and replacing a path with
+        // another flight
+        manifest.sort();
+        assert_eq!(manifest.get(path).unwrap().callsign, "AM");
+        let mut replacement = entry("CX", "dd");
+        replacement.path = PathBuf::from("club/2025-06-19/56J_AM.igc");
+        manifest.insert(replacement);
+        assert_eq!(manifest.get(path).unwrap().callsign, "CX");
+        assert_eq!(manifest.flight(&daily_info, "CX").unwrap().sha256, "dd");
+        assert_eq!(manifest.flight(&daily_info, "AM"), None);
+        assert_eq!(manifest.flight(&daily_info, "A3").unwrap().sha256, "bb");
+    }
+
+    #[test]
+    fn test_manifest_days() {
+        let daily_info = DailyUrlInfo {
+            language: "en_gb".to_string(),
+            competition: "39th-fai-world-gliding-championships-tabor-2025".to_string(),
+            class: "club".to_string(),
+            date: Date::constant(2025, 6, 19),
+            task_name: "task-10".to_string(),
+        };
+
+        let mut manifest = Manifest::new(&daily_info.competition);
+        assert_eq!(manifest.day(&daily_info), None);
+
+        manifest.insert_day(&daily_info, CacheValidators::default());
+        let validators = CacheValidators {
+            etag: None,
+            last_modified: Some("Thu, 19 Jun 2025 18:30:00 GMT".to_string()),
+        };
+        manifest.insert_day(&daily_info, validators.clone());
+
+        assert_eq!(manifest.days.len(), 1);
+        let day = manifest.day(&daily_info).unwrap();
+        assert_eq!(day.validators, validators);
+        assert_eq!(day.language.as_deref(), Some("en_gb"));
+
+        // The day is found whatever language it is requested in
+        let daily_info = DailyUrlInfo {
+            language: "cs".to_string(),
+            ..daily_info
+        };
+        assert_eq!(manifest.day(&daily_info), Some(day));
+    }
+
+    #[tokio::test]
+    async fn test_manifest_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(MANIFEST_FILENAME);
+        assert_eq!(Manifest::load(&path).await.unwrap(), None);
+
+        let mut manifest = Manifest::new("39th-fai-world-gliding-championships-tabor-2025");
+        let mut entry = entry("AM", "aa");
+        entry.validators.etag = Some("\"5039-1\"".to_string());
+        manifest.insert(entry);
+        manifest.write(&path).await.unwrap();
+
+        insta::assert_snapshot!(std::fs::read_to_string(&path).unwrap(), @r#"
+        {
+          "competition": "39th-fai-world-gliding-championships-tabor-2025",
+          "days": [],
+          "files": [
+            {
+              "class": "club",
+              "date": "2025-06-19",
+              "task_name": "task-10",
+              "callsign": "AM",
+              "language": "en_gb",
+              "source_url": "https://www.soaringspot.com/en_gb/download-contest-flight/5039-10179576303?dl=1",
+              "path": "club/2025-06-19/56J_AM.igc",
+              "size": 42,
+              "sha256": "aa",
+              "downloaded_at": "2025-06-19T18:30:00Z",
+              "etag": "\"5039-1\""
+            }
+          ]
+        }
+        "#);
+        assert_eq!(Manifest::load(&path).await.unwrap(), Some(manifest));
+    }
+}
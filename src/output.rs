@@ -0,0 +1,58 @@
+use crate::error::Error;
+use serde::Serialize;
+
+/// Formats that lists like the plan of a dry run can be printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for reading
+    Table,
+    /// JSON array for scripts
+    Json,
+}
+
+/// An item that can be printed as a row of a table.
+pub trait TableRow {
+    /// Column names
+    const HEADER: &'static [&'static str];
+
+    /// Values of the columns, in the order of [TableRow::HEADER]
+    fn cells(&self) -> Vec<String>;
+}
+
+/// Formats `items` as a table with aligned columns, or as a JSON array.
+pub fn format_output<T: TableRow + Serialize>(
+    items: &[T],
+    format: OutputFormat,
+) -> Result<String, Error> {
+    match format {
+        OutputFormat::Table => Ok(format_table(items)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(items)?),
+    }
+}
+
+fn format_table<T: TableRow>(items: &[T]) -> String {
+    let header = T::HEADER.iter().map(|name| name.to_string()).collect();
+    let rows = std::iter::once(header)
+        .chain(items.iter().map(TableRow::cells))
+        .collect::<Vec<Vec<_>>>();
+
+    let mut widths = vec![0; T::HEADER.len()];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
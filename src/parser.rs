@@ -1,6 +1,12 @@
-use crate::url_utils::{DailyUrlInfo, UrlInfo, extract_url_info};
+use crate::error::Error;
+use crate::task::{
+    ObservationZone, Task, TaskKind, Turnpoint, TurnpointKind, min_time_from_info, parse_coordinate,
+};
+use crate::url_utils::{DEFAULT_LANGUAGE, DailyUrlInfo, UrlInfo, extract_url_info, resolve_url};
 use html_escape::decode_html_entities;
-use scraper::{Html, Selector};
+use jiff::civil::Time;
+use scraper::{ElementRef, Html, Selector};
+use std::time::Duration;
 use url::Url;
 
 #[derive(Debug, Clone)]
@@ -9,18 +15,29 @@ pub struct IgcFile {
     pub download_url: String,
 }
 
-pub fn parse_igc_files(html: &str) -> Result<Vec<IgcFile>, Box<dyn std::error::Error>> {
+/// Extracts the IGC download links from a daily results page, resolved
+/// against `base_url`.
+pub fn parse_igc_files(html: &str, base_url: &Url) -> Result<Vec<IgcFile>, Error> {
     let document = Html::parse_document(html);
     let mut igc_files = Vec::new();
 
+    // Make sure we are looking at a daily results page at all, otherwise a
+    // markup change would silently result in "no files found"
+    let table_selector = parse_selector("table.result-daily")?;
+    if document.select(&table_selector).next().is_none() {
+        return Err(Error::HtmlStructure(
+            "daily results table not found".to_string(),
+        ));
+    }
+
     // Select all elements with data-content attributes containing download links
-    let selector = Selector::parse(r#"[data-content*="download-contest-flight"]"#)?;
+    let selector = parse_selector(r#"[data-content*="download-contest-flight"]"#)?;
 
     for element in document.select(&selector) {
         // Extract the data-content attribute
         if let Some(data_content) = element.value().attr("data-content") {
             // Decode HTML entities and extract the download URL
-            if let Some(download_url) = extract_download_url(data_content) {
+            if let Some(download_url) = extract_download_url(data_content, base_url) {
                 // Get the callsign from the text content of this cell
                 let callsign = element.text().collect::<String>().trim().to_string();
 
@@ -37,7 +54,7 @@ pub fn parse_igc_files(html: &str) -> Result<Vec<IgcFile>, Box<dyn std::error::E
     Ok(igc_files)
 }
 
-fn extract_download_url(data_content: &str) -> Option<String> {
+fn extract_download_url(data_content: &str, base_url: &Url) -> Option<String> {
     // The data_content contains HTML-encoded content
     // We need to decode it and extract the download URL with ?dl=1
 
@@ -56,30 +73,625 @@ fn extract_download_url(data_content: &str) -> Option<String> {
         .select(&link_selector)
         .next()
         .and_then(|element| element.value().attr("href"))
-        .map(|href| format!("https://www.soaringspot.com{}", href))
+        .map(|href| resolve_url(base_url, href))
+}
+
+/// A single row of the daily results table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyResult {
+    /// Placement on the day, `None` for contestants without a rank (see `status`)
+    pub rank: Option<u32>,
+    /// Status shown instead of a rank, e.g. "DNF"
+    pub status: Option<String>,
+    pub callsign: String,
+    /// Download URL of the IGC file, if one was uploaded
+    pub download_url: Option<String>,
+    pub pilot: String,
+    /// Country code of the nationality flag, e.g. "GB"
+    pub nationality: Option<String>,
+    pub glider: Option<String>,
+    pub handicap: Option<f64>,
+    pub start: Option<Time>,
+    pub finish: Option<Time>,
+    /// Task time
+    pub time: Option<Duration>,
+    /// Task speed in km/h
+    pub speed: Option<f64>,
+    /// Task distance in km
+    pub distance: Option<f64>,
+    pub points: Option<u32>,
+    /// Penalties from the tooltip shown next to the points
+    pub penalties: Vec<Penalty>,
+    /// Annotation from the comments below the results table, e.g. "DNF"
+    pub comment: Option<String>,
+}
+
+/// A penalty applied to a daily result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Penalty {
+    /// Reason for the penalty, e.g. "Finish altitude 545m"
+    pub reason: String,
+    /// Number of points deducted
+    pub points: Option<u32>,
+}
+
+impl Penalty {
+    /// Labels of the total in a penalty, like "(105 penalty points)", in
+    /// English or one of the other languages SoaringSpot is commonly used in.
+    const POINTS_LABELS: [&str; 10] = [
+        "penalty points",
+        "strafpunkte",
+        "trestné body",
+        "trestných bodů",
+        "points de pénalité",
+        "punti di penalità",
+        "puntos de penalización",
+        "pontos de penalidade",
+        "strafpunten",
+        "punkty karne",
+    ];
+
+    /// Parses a penalty tooltip entry like
+    /// "Finish altitude 545m (-105) (105 penalty points)".
+    ///
+    /// The deduction like "(-105)" is understood in any language. If the
+    /// points can't be found, the whole text is kept as the reason.
+    pub fn parse(text: &str) -> Self {
+        let mut reason = text.trim();
+        let mut points = None;
+
+        if let Some(rest) = reason.strip_suffix(')')
+            && let Some((head, total)) = rest.rsplit_once('(')
+            && let Some((value, label)) = total.trim().split_once(' ')
+            && Self::POINTS_LABELS.contains(&label.trim().to_lowercase().as_str())
+            && let Ok(value) = value.parse()
+        {
+            points = Some(value);
+            reason = head.trim_end();
+        }
+
+        if let Some(rest) = reason.strip_suffix(')')
+            && let Some((head, value)) = rest.rsplit_once("(-")
+            && let Ok(value) = value.trim().parse()
+        {
+            points = points.or(Some(value));
+            reason = head.trim_end();
+        }
+
+        Penalty {
+            reason: reason.to_string(),
+            points,
+        }
+    }
+}
+
+/// Columns of the daily results table that are understood by
+/// [parse_daily_result_table].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Rank,
+    Callsign,
+    Contestant,
+    Glider,
+    Handicap,
+    Start,
+    Finish,
+    Time,
+    Speed,
+    Distance,
+    Points,
+}
+
+impl Column {
+    /// Identifies a column by its header text, in English or one of the
+    /// other languages SoaringSpot is commonly used in.
+    fn from_header(header: &str) -> Option<Self> {
+        Some(match header.to_lowercase().as_str() {
+            "#" => Column::Rank,
+            "cn" => Column::Callsign,
+            "contestant" | "pilot" | "teilnehmer" | "závodník" | "concurrent" | "pilote"
+            | "concorrente" | "pilota" | "competidor" | "piloto" | "deelnemer" | "piloot"
+            | "zawodnik" => Column::Contestant,
+            "glider" | "flugzeug" | "kluzák" | "planeur" | "aliante" | "planeador"
+            | "zweefvliegtuig" | "szybowiec" => Column::Glider,
+            "handicap" | "index" | "indeks" => Column::Handicap,
+            "start" | "abflug" | "départ" | "partenza" | "salida" => Column::Start,
+            "finish" | "ziel" | "cíl" | "arrivée" | "arrivo" | "llegada" | "meta" => {
+                Column::Finish
+            }
+            "time" | "zeit" | "čas" | "temps" | "tempo" | "tiempo" | "tijd" | "czas" => {
+                Column::Time
+            }
+            "speed" | "geschwindigkeit" | "rychlost" | "vitesse" | "velocità" | "velocidad"
+            | "snelheid" | "prędkość" => Column::Speed,
+            "distance" | "strecke" | "distanz" | "vzdálenost" | "distanza" | "distancia"
+            | "afstand" | "dystans" | "odległość" => Column::Distance,
+            "points" | "punkte" | "body" | "punti" | "puntos" | "punten" | "punkty" => {
+                Column::Points
+            }
+            _ => return None,
+        })
+    }
+}
+
+/// Parses the full results table of a daily results page.
+///
+/// Columns are identified by their header, so that optional columns like
+/// "Glider" or "Handicap" may be missing.
+pub fn parse_daily_result_table(html: &str, base_url: &Url) -> Result<Vec<DailyResult>, Error> {
+    let document = Html::parse_document(html);
+
+    let table_selector = parse_selector("table.result-daily")?;
+    let Some(table) = document.select(&table_selector).next() else {
+        return Err(Error::HtmlStructure(
+            "daily results table not found".to_string(),
+        ));
+    };
+
+    let header_selector = parse_selector("thead th")?;
+    let mut columns = table
+        .select(&header_selector)
+        .map(|th| {
+            // The points column is sorted by default, whatever it is called
+            Column::from_header(&element_text(th))
+                .or_else(|| th.value().attr("data-sort-initial").map(|_| Column::Points))
+        })
+        .collect::<Vec<_>>();
+
+    let row_selector = parse_selector("tbody > tr")?;
+    let cell_selector = parse_selector(":scope > td")?;
+    let flag_selector = parse_selector(".flag")?;
+    let penalty_selector = parse_selector(".penalty-points")?;
+    let download_selector = parse_selector(r#"[data-content*="download-contest-flight"]"#)?;
+
+    // Headers of localised pages may be unknown, so fall back to the cell
+    // content: the callsign has the download popover, the contestant the flag
+    for (column, selector) in [
+        (Column::Callsign, &download_selector),
+        (Column::Contestant, &flag_selector),
+    ] {
+        if columns.contains(&Some(column)) {
+            continue;
+        }
+
+        let index = table.select(&row_selector).find_map(|row| {
+            row.select(&cell_selector)
+                .position(|cell| cell.select(selector).next().is_some())
+        });
+        if let Some(slot) = index.and_then(|index| columns.get_mut(index))
+            && slot.is_none()
+        {
+            *slot = Some(column);
+        }
+    }
+
+    if !columns.contains(&Some(Column::Callsign)) {
+        return Err(Error::HtmlStructure(
+            "daily results table has no CN column".to_string(),
+        ));
+    }
+
+    let numbers = table
+        .select(&row_selector)
+        .flat_map(|row| row.select(&cell_selector).zip(&columns))
+        .filter(|(_, column)| {
+            matches!(
+                column,
+                Some(Column::Handicap | Column::Speed | Column::Distance | Column::Points)
+            )
+        })
+        .map(|(cell, _)| element_text(cell))
+        .collect::<Vec<_>>();
+    let separator = DecimalSeparator::of_page(&document, numbers.iter().map(String::as_str));
+
+    let mut results = Vec::new();
+    for row in table.select(&row_selector) {
+        let mut result = DailyResult {
+            rank: None,
+            status: None,
+            callsign: String::new(),
+            download_url: None,
+            pilot: String::new(),
+            nationality: None,
+            glider: None,
+            handicap: None,
+            start: None,
+            finish: None,
+            time: None,
+            speed: None,
+            distance: None,
+            points: None,
+            penalties: vec![],
+            comment: None,
+        };
+
+        for (cell, column) in row.select(&cell_selector).zip(&columns) {
+            let Some(column) = column else {
+                continue;
+            };
+
+            let text = element_text(cell);
+            match column {
+                Column::Rank => match text.trim_end_matches('.').parse() {
+                    Ok(rank) => result.rank = Some(rank),
+                    Err(_) => result.status = non_empty(text),
+                },
+                Column::Callsign => {
+                    result.download_url = cell
+                        .select(&download_selector)
+                        .next()
+                        .and_then(|element| element.value().attr("data-content"))
+                        .and_then(|data_content| extract_download_url(data_content, base_url));
+                    result.callsign = text;
+                }
+                Column::Contestant => {
+                    result.nationality = cell
+                        .select(&flag_selector)
+                        .next()
+                        .and_then(|flag| flag.value().attr("title"))
+                        .and_then(|title| non_empty(title.trim().to_uppercase()));
+                    result.pilot = text;
+                }
+                Column::Glider => result.glider = non_empty(text),
+                Column::Handicap => result.handicap = parse_number(&text, separator),
+                Column::Start => result.start = parse_time(&text),
+                Column::Finish => result.finish = parse_time(&text),
+                Column::Time => result.time = parse_duration(&text),
+                Column::Speed => result.speed = parse_number(&text, separator),
+                Column::Distance => result.distance = parse_number(&text, separator),
+                Column::Points => {
+                    // Cells without a number, like "-", have no points
+                    if text.contains(|c: char| c.is_ascii_digit()) {
+                        let points = parse_integer(&text, separator).ok_or_else(|| {
+                            Error::HtmlStructure(format!("invalid points '{text}'"))
+                        })?;
+                        result.points = Some(points);
+                    }
+                    result.penalties = cell
+                        .select(&penalty_selector)
+                        .filter_map(|element| element.value().attr("data-content"))
+                        .flat_map(|content| content.split(['\n', '\r']))
+                        .flat_map(|line| line.split("<br>"))
+                        .filter(|line| !line.trim().is_empty())
+                        .map(Penalty::parse)
+                        .collect();
+                }
+            }
+        }
+
+        if !result.callsign.is_empty() {
+            results.push(result);
+        }
+    }
+
+    // Comments are listed below the table, e.g.
+    // `<span>CX</span><span> - Finish altitude 545m (-105)</span>`
+    let comment_selector = parse_selector(".result-comment")?;
+    let span_selector = parse_selector(":scope > span")?;
+    for comment in document.select(&comment_selector) {
+        let mut spans = comment.select(&span_selector).map(element_text);
+        let (Some(callsign), Some(text)) = (spans.next(), spans.next()) else {
+            continue;
+        };
+        let Some(text) = non_empty(text.trim_start_matches('-').trim().to_string()) else {
+            continue;
+        };
+
+        for result in results
+            .iter_mut()
+            .filter(|result| result.callsign == callsign)
+        {
+            result.comment = Some(match result.comment.take() {
+                Some(existing) => format!("{existing}; {text}"),
+                None => text.clone(),
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+/// Maps a header of the task turnpoints table to its English name.
+fn task_column(header: &str) -> Option<&'static str> {
+    Some(match header {
+        "type" | "typ" | "tipo" | "tipe" => "type",
+        "name" | "název" | "nom" | "nome" | "nombre" | "naam" | "nazwa" => "name",
+        "latitude"
+        | "breite"
+        | "zeměpisná šířka"
+        | "latitudine"
+        | "latitud"
+        | "breedtegraad"
+        | "szerokość" => "latitude",
+        "longitude" | "länge" | "zeměpisná délka" | "longitudine" | "longitud" | "lengtegraad"
+        | "długość" => "longitude",
+        "observation zone"
+        | "beobachtungszone"
+        | "zóna"
+        | "zone d'observation"
+        | "zona di osservazione"
+        | "zona de observación"
+        | "sector"
+        | "strefa" => "observation zone",
+        "distance" | "strecke" | "distanz" | "vzdálenost" | "distanza" | "distancia"
+        | "afstand" | "dystans" | "odległość" => "distance",
+        _ => return None,
+    })
+}
+
+/// Parses the task page of a single task day.
+pub fn parse_task(html: &str) -> Result<Task, Error> {
+    let document = Html::parse_document(html);
+
+    let table_selector = parse_selector("table.task-turnpoints")?;
+    let Some(table) = document.select(&table_selector).next() else {
+        return Err(Error::HtmlStructure(
+            "task turnpoints table not found".to_string(),
+        ));
+    };
+
+    // Task summary, e.g. `<span>Task info:</span><span>Racing Task, ...</span>`
+    let value_selector = parse_selector("span:last-of-type")?;
+    let info_value = |class: &str| -> Result<Option<String>, Error> {
+        let selector = parse_selector(&format!(".result-info.{class}"))?;
+        Ok(document
+            .select(&selector)
+            .next()
+            .and_then(|info| info.select(&value_selector).next())
+            .and_then(|value| non_empty(element_text(value))))
+    };
+
+    let info = info_value("task-info")?;
+    let task_distance = info_value("task-distance")?;
+    let kind = info
+        .as_deref()
+        .map_or(TaskKind::Other(String::new()), TaskKind::from_info);
+    let min_time = info.as_deref().and_then(min_time_from_info);
+
+    let header_selector = parse_selector("thead th")?;
+    let headers = table
+        .select(&header_selector)
+        .map(|th| task_column(&element_text(th).to_lowercase()))
+        .collect::<Vec<_>>();
+    let column = |name: &str| headers.iter().position(|header| *header == Some(name));
+
+    let row_selector = parse_selector("tbody > tr")?;
+    let cell_selector = parse_selector(":scope > td")?;
+
+    // Headers of localised pages may be unknown, so fall back to the first
+    // cells that contain coordinates, with the name right before them
+    let first_row = table
+        .select(&row_selector)
+        .next()
+        .map(|row| {
+            row.select(&cell_selector)
+                .map(element_text)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let coordinate_column = |hemispheres: [char; 2]| {
+        first_row
+            .iter()
+            .position(|text| text.ends_with(hemispheres) && parse_coordinate(text).is_some())
+    };
+    let latitude_column = column("latitude").or_else(|| coordinate_column(['N', 'S']));
+    let longitude_column = column("longitude").or_else(|| coordinate_column(['E', 'W']));
+    let name_column = column("name").or_else(|| latitude_column?.checked_sub(1));
+
+    let (Some(name_column), Some(latitude_column), Some(longitude_column)) =
+        (name_column, latitude_column, longitude_column)
+    else {
+        return Err(Error::HtmlStructure(
+            "task turnpoints table is missing name or coordinate columns".to_string(),
+        ));
+    };
+    let type_column = column("type");
+    let zone_column = column("observation zone");
+    let distance_column = column("distance");
+
+    let rows = table.select(&row_selector).collect::<Vec<_>>();
+    let leg_distances = rows
+        .iter()
+        .filter_map(|row| row.select(&cell_selector).nth(distance_column?))
+        .map(element_text)
+        .collect::<Vec<_>>();
+    let numbers = task_distance.iter().chain(&leg_distances);
+    let separator = DecimalSeparator::of_page(&document, numbers.map(String::as_str));
+    let distance = task_distance.and_then(|text| parse_number(&text, separator));
+    let mut turnpoints = Vec::with_capacity(rows.len());
+    for (index, row) in rows.iter().enumerate() {
+        let cells = row
+            .select(&cell_selector)
+            .map(element_text)
+            .collect::<Vec<_>>();
+        let cell = |column: Option<usize>| column.and_then(|column| cells.get(column));
+
+        let name = cell(Some(name_column)).cloned().unwrap_or_default();
+        let coordinate = |column: usize| {
+            let text = cell(Some(column)).map(String::as_str).unwrap_or_default();
+            parse_coordinate(text).ok_or_else(|| {
+                Error::HtmlStructure(format!(
+                    "invalid coordinate '{text}' for turnpoint '{name}'"
+                ))
+            })
+        };
+
+        // Fall back to the position in the task if the type is unknown
+        let kind = match cell(type_column).and_then(|text| TurnpointKind::from_type(text)) {
+            Some(kind) => kind,
+            None if index == 0 => TurnpointKind::Start,
+            None if index == rows.len() - 1 => TurnpointKind::Finish,
+            None => TurnpointKind::Turnpoint,
+        };
+
+        turnpoints.push(Turnpoint {
+            kind,
+            latitude: coordinate(latitude_column)?,
+            longitude: coordinate(longitude_column)?,
+            observation_zone: ObservationZone::parse(
+                cell(zone_column).map(String::as_str).unwrap_or_default(),
+            ),
+            leg_distance: cell(distance_column).and_then(|text| parse_number(text, separator)),
+            name,
+        });
+    }
+
+    Ok(Task {
+        kind,
+        distance,
+        min_time,
+        info,
+        turnpoints,
+    })
+}
+
+/// Returns the whitespace-normalized text content of an element.
+fn element_text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn non_empty(text: String) -> Option<String> {
+    (!text.is_empty()).then_some(text)
+}
+
+/// Decimal separator of the numbers on a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DecimalSeparator {
+    /// Numbers like "1,234.5"
+    Point,
+    /// Numbers like "1.234,5"
+    Comma,
+}
+
+impl DecimalSeparator {
+    /// Languages that use a decimal point, all others use a decimal comma.
+    const POINT_LANGUAGES: [&str; 6] = ["en", "he", "ja", "ko", "th", "zh"];
+
+    /// Returns the separator of the `numbers` on a page, or the one of its
+    /// language, like "en_gb" or "cs" in `<html lang="cs">`, if none of them
+    /// tells.
+    fn of_page<'a>(document: &Html, numbers: impl IntoIterator<Item = &'a str>) -> Self {
+        Self::detect(numbers).unwrap_or_else(|| {
+            let language = document.root_element().value().attr("lang");
+            Self::for_language(language.unwrap_or(DEFAULT_LANGUAGE))
+        })
+    }
+
+    /// Detects the separator from the first number that can only be read one
+    /// way, like "100.66", "1.234,5" or "1.234.567". Numbers like "1,000" or
+    /// "1.062" could be either.
+    fn detect<'a>(numbers: impl IntoIterator<Item = &'a str>) -> Option<Self> {
+        numbers.into_iter().find_map(|text| {
+            let number = text
+                .chars()
+                .skip_while(|c| !c.is_ascii_digit())
+                .take_while(|c| c.is_ascii_digit() || matches!(c, '.' | ','))
+                .collect::<String>();
+            let number = number.trim_end_matches(['.', ',']);
+            let index = number.rfind(['.', ','])?;
+            let (last, other) = match &number[index..=index] {
+                "." => (DecimalSeparator::Point, DecimalSeparator::Comma),
+                _ => (DecimalSeparator::Comma, DecimalSeparator::Point),
+            };
+
+            if number[..index].contains(['.', ',']) {
+                // The last separator is the decimal one if both are used,
+                // otherwise they all separate digit groups
+                let (decimal, _) = last.chars();
+                return Some(match number[..index].contains(decimal) {
+                    true => other,
+                    false => last,
+                });
+            }
+            (number.len() - index - 1 != 3).then_some(last)
+        })
+    }
+
+    fn for_language(language: &str) -> Self {
+        let language = language.split(['_', '-']).next().unwrap_or_default();
+        let language = language.to_ascii_lowercase();
+        if Self::POINT_LANGUAGES.contains(&language.as_str()) {
+            DecimalSeparator::Point
+        } else {
+            DecimalSeparator::Comma
+        }
+    }
+
+    /// Returns the decimal separator and the separator of digit groups.
+    fn chars(self) -> (char, char) {
+        match self {
+            DecimalSeparator::Point => ('.', ','),
+            DecimalSeparator::Comma => (',', '.'),
+        }
+    }
+}
+
+/// Parses the leading number of texts like "100.66 km/h" or "1,000", or
+/// "100,66 km/h" and "1.000" on pages with a decimal comma.
+fn parse_number(text: &str, separator: DecimalSeparator) -> Option<f64> {
+    let (decimal, group) = separator.chars();
+    let number = text
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == decimal || *c == group)
+        .filter(|c| *c != group)
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect::<String>();
+    number.parse().ok()
+}
+
+/// Parses whole numbers like "1,000", or "1.000" on pages with a decimal
+/// comma.
+fn parse_integer(text: &str, separator: DecimalSeparator) -> Option<u32> {
+    let (_, group) = separator.chars();
+    text.chars()
+        .filter(|c| *c != group)
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+/// Parses times of day like "12:23:59".
+fn parse_time(text: &str) -> Option<Time> {
+    Time::strptime("%H:%M:%S", text).ok()
+}
+
+/// Parses durations like "4:18:18".
+fn parse_duration(text: &str) -> Option<Duration> {
+    let mut parts = text.split(':').map(|part| part.parse::<u64>().ok());
+    let (Some(Some(hours)), Some(Some(minutes)), Some(Some(seconds)), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
 }
 
 /// Extracts all daily result URLs from a competition results page.
 ///
 /// Returns a list of [DailyUrlInfo] for each class and task.
-pub fn parse_daily_results(html: &str) -> Result<Vec<DailyUrlInfo>, Box<dyn std::error::Error>> {
+pub fn parse_daily_results(html: &str, base_url: &Url) -> Result<Vec<DailyUrlInfo>, Error> {
     let document = Html::parse_document(html);
     let mut daily_results = Vec::new();
 
     // Select all links that point to daily results
     // Looking for: /en_gb/{competition}/results/{class}/task-{n}-on-{date}/daily
-    let selector = Selector::parse(r#"a[href*="/results/"][href*="/daily"]"#)?;
+    let selector = parse_selector(r#"a[href*="/results/"][href*="/daily"]"#)?;
 
     for element in document.select(&selector) {
         if let Some(href) = element.value().attr("href") {
             // Construct full URL
-            let full_url = format!("https://www.soaringspot.com{}", href);
+            let full_url = resolve_url(base_url, href);
 
             // Parse the URL to extract info
-            if let Ok(url) = Url::parse(&full_url) {
-                if let Ok(UrlInfo::Daily(daily_info)) = extract_url_info(&url) {
-                    daily_results.push(daily_info);
-                }
+            if let Ok(url) = Url::parse(&full_url)
+                && let Ok(UrlInfo::Daily(daily_info)) = extract_url_info(&url, base_url)
+            {
+                daily_results.push(daily_info);
             }
         }
     }
@@ -87,22 +699,264 @@ pub fn parse_daily_results(html: &str) -> Result<Vec<DailyUrlInfo>, Box<dyn std:
     Ok(daily_results)
 }
 
+fn parse_selector(selector: &str) -> Result<Selector, Error> {
+    Selector::parse(selector)
+        .map_err(|e| Error::HtmlStructure(format!("invalid selector '{selector}': {e}")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::url_utils::default_base_url;
 
     #[test]
     fn test_parse_igc_files_snapshot() {
         let html = include_str!("../tests/fixtures/day.html");
-        let igc_files = parse_igc_files(html).expect("Failed to parse IGC files");
+        let igc_files =
+            parse_igc_files(html, &default_base_url()).expect("Failed to parse IGC files");
 
         insta::assert_debug_snapshot!(igc_files);
     }
 
+    #[test]
+    fn test_parse_igc_files_base_url() {
+        let html = include_str!("../tests/fixtures/day.html");
+        let base_url = Url::parse("http://localhost:8080/").unwrap();
+        let igc_files = parse_igc_files(html, &base_url).unwrap();
+        insta::assert_snapshot!(igc_files[0].download_url, @"http://localhost:8080/en_gb/download-contest-flight/5039-10179576293?dl=1");
+    }
+
+    #[test]
+    fn test_parse_igc_files_unexpected_markup() {
+        let html = include_str!("../tests/fixtures/results.html");
+        let result = parse_igc_files(html, &default_base_url());
+        insta::assert_snapshot!(result.unwrap_err(), @"Unexpected HTML structure: daily results table not found");
+    }
+
+    #[test]
+    fn test_parse_daily_result_table() {
+        let html = include_str!("../tests/fixtures/day.html");
+        let results = parse_daily_result_table(html, &default_base_url()).unwrap();
+        insta::assert_debug_snapshot!(results);
+    }
+
+    #[test]
+    fn test_parse_penalty() {
+        assert_eq!(
+            Penalty::parse("Finish altitude 545m (-105) (105 penalty points)"),
+            Penalty {
+                reason: "Finish altitude 545m".to_string(),
+                points: Some(105),
+            }
+        );
+        assert_eq!(
+            Penalty::parse("Start outside PEV interval (-6)"),
+            Penalty {
+                reason: "Start outside PEV interval".to_string(),
+                points: Some(6),
+            }
+        );
+        assert_eq!(
+            Penalty::parse("Airspace infringement"),
+            Penalty {
+                reason: "Airspace infringement".to_string(),
+                points: None,
+            }
+        );
+
+        // Localised pages
+        assert_eq!(
+            Penalty::parse("Zielhöhe 545m (-105) (105 Strafpunkte)"),
+            Penalty {
+                reason: "Zielhöhe 545m".to_string(),
+                points: Some(105),
+            }
+        );
+        assert_eq!(
+            Penalty::parse("Výška v cíli 545m (-105) (105 neznámé body)"),
+            Penalty {
+                reason: "Výška v cíli 545m (-105) (105 neznámé body)".to_string(),
+                points: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_localised_daily_result_table() {
+        let html = include_str!("../tests/fixtures/day.html");
+        let english = parse_daily_result_table(html, &default_base_url()).unwrap();
+
+        // Unknown headers fall back to the structure of the table
+        let mut localised = html.to_string();
+        for (header, translation) in [
+            (">CN<", ">Vers.<"),
+            (">Contestant<", ">Versenyző<"),
+            (">Glider<", ">Flugzeug<"),
+            (">Time<", ">Zeit<"),
+            (">Speed<", ">Geschwindigkeit<"),
+            (">Distance<", ">Strecke<"),
+            (">Points<", ">Pontok<"),
+        ] {
+            localised = localised.replace(header, translation);
+        }
+
+        assert_eq!(
+            parse_daily_result_table(&localised, &default_base_url()).unwrap(),
+            english
+        );
+    }
+
+    /// Writes the numbers of a page like "1.062" or "1,000" as "1,062" and
+    /// "1.000", and declares the page as Czech.
+    fn with_decimal_comma(html: &str) -> String {
+        let html = html.replace("<html lang=\"en_gb\">", "<html lang=\"cs\">");
+        let mut segments = html.split('>').map(|segment| {
+            let (text, rest) = segment.split_once('<').unwrap_or((segment, ""));
+            let trimmed = text.trim();
+            if !trimmed.starts_with(|c: char| c.is_ascii_digit()) || trimmed.ends_with('.') {
+                return segment.to_string();
+            }
+            let text = text
+                .chars()
+                .map(|c| match c {
+                    '.' => ',',
+                    ',' => '.',
+                    c => c,
+                })
+                .collect::<String>();
+            format!("{text}<{rest}")
+        });
+        let first = segments.next().unwrap_or_default();
+        segments.fold(first, |html, segment| html + ">" + &segment)
+    }
+
+    #[test]
+    fn test_parse_decimal_comma_daily_result_table() {
+        let html = include_str!("../tests/fixtures/day.html");
+        let english = parse_daily_result_table(html, &default_base_url()).unwrap();
+        let czech = with_decimal_comma(html);
+        assert_ne!(czech, html);
+        assert_eq!(
+            parse_daily_result_table(&czech, &default_base_url()).unwrap(),
+            english
+        );
+    }
+
+    #[test]
+    fn test_decimal_separator_for_language() {
+        assert_eq!(
+            DecimalSeparator::for_language("en_gb"),
+            DecimalSeparator::Point
+        );
+        assert_eq!(
+            DecimalSeparator::for_language("cs"),
+            DecimalSeparator::Comma
+        );
+        assert_eq!(
+            DecimalSeparator::for_language("de-DE"),
+            DecimalSeparator::Comma
+        );
+    }
+
+    #[test]
+    fn test_detect_decimal_separator() {
+        use DecimalSeparator::{Comma, Point};
+
+        assert_eq!(DecimalSeparator::detect(["100.66 km/h"]), Some(Point));
+        assert_eq!(DecimalSeparator::detect(["100,66 km/h"]), Some(Comma));
+        assert_eq!(DecimalSeparator::detect(["1.234,5 km"]), Some(Comma));
+        assert_eq!(DecimalSeparator::detect(["1,234.5 km"]), Some(Point));
+        assert_eq!(DecimalSeparator::detect(["1.234.567"]), Some(Comma));
+        assert_eq!(DecimalSeparator::detect(["Line 10.00 km"]), Some(Point));
+
+        // Numbers that could be read both ways are skipped
+        assert_eq!(DecimalSeparator::detect(["1,000", "1.062", "991"]), None);
+        assert_eq!(DecimalSeparator::detect(["1,000", "433.33"]), Some(Point));
+    }
+
+    #[test]
+    fn test_parse_point_decimal_page_in_other_language() {
+        // Spanish uses a decimal comma in Spain but a decimal point in
+        // Mexico, so the numbers on the page decide
+        let html = include_str!("../tests/fixtures/day.html");
+        let english = parse_daily_result_table(html, &default_base_url()).unwrap();
+        let mexican = html.replacen(r#"<html lang="en_gb">"#, r#"<html lang="es_MX">"#, 1);
+        assert_ne!(mexican, html);
+        assert_eq!(
+            parse_daily_result_table(&mexican, &default_base_url()).unwrap(),
+            english
+        );
+    }
+
+    #[test]
+    fn test_invalid_points() {
+        let html = include_str!("../tests/fixtures/day.html");
+        let html = html.replacen("1,000", "1,000,000,000,000", 1);
+        insta::assert_snapshot!(
+            parse_daily_result_table(&html, &default_base_url()).unwrap_err(),
+            @"Unexpected HTML structure: invalid points '1,000,000,000,000'"
+        );
+    }
+
+    #[test]
+    fn test_parse_number() {
+        use DecimalSeparator::{Comma, Point};
+
+        assert_eq!(parse_number("100.66 km/h", Point), Some(100.66));
+        assert_eq!(parse_number("1,000", Point), Some(1000.));
+        assert_eq!(parse_number("1,234.5 km", Point), Some(1234.5));
+        assert_eq!(parse_number("100,66 km/h", Comma), Some(100.66));
+        assert_eq!(parse_number("512,345 km", Comma), Some(512.345));
+        assert_eq!(parse_number("1.234,5 km", Comma), Some(1234.5));
+        assert_eq!(parse_number("-", Point), None);
+
+        assert_eq!(parse_integer("1,000", Point), Some(1000));
+        assert_eq!(parse_integer("1.000", Comma), Some(1000));
+        assert_eq!(parse_integer("1,5", Comma), None);
+        assert_eq!(parse_integer("99999999999", Point), None);
+    }
+
+    #[test]
+    fn test_parse_localised_task() {
+        let html = include_str!("../tests/fixtures/task.html");
+        let english = parse_task(html).unwrap();
+
+        let mut localised = html.to_string();
+        for (text, translation) in [
+            (">Type<", ">Typ<"),
+            (">Latitude<", ">Breite<"),
+            (">Longitude<", ">Länge<"),
+            (">Observation zone<", ">Beobachtungszone<"),
+            (">Distance<", ">Strecke<"),
+            (">Start<", ">Abflug<"),
+            (">Turnpoint<", ">Wendepunkt<"),
+            (">Finish<", ">Ziel<"),
+            (">Line 10.00", ">Linie 10,00"),
+            (">Cylinder 0.50", ">Zylinder 0,50"),
+            (">Sector", ">Sektor"),
+        ] {
+            localised = localised.replace(text, translation);
+        }
+        // Without a name header, the name is taken from before the coordinates
+        localised = localised.replace(">Name<", ">Bezeichnung<");
+
+        assert_eq!(
+            parse_task(&localised).unwrap().turnpoints,
+            english.turnpoints
+        );
+    }
+
+    #[test]
+    fn test_parse_task() {
+        let html = include_str!("../tests/fixtures/task.html");
+        let task = parse_task(html).unwrap();
+        insta::assert_debug_snapshot!(task);
+    }
+
     #[test]
     fn test_parse_daily_results() {
         let html = include_str!("../tests/fixtures/results.html");
-        let daily_results = parse_daily_results(html).unwrap();
+        let daily_results = parse_daily_results(html, &default_base_url()).unwrap();
 
         // Should find tasks for all three classes: club, standard, 15-meter
         // Each class should have 11 tasks (excluding practice tasks)
@@ -111,4 +965,21 @@ mod tests {
         // Snapshot test for the structure
         insta::assert_debug_snapshot!(daily_results);
     }
+
+    #[test]
+    fn test_parse_daily_results_absolute_links() {
+        let html = include_str!("../tests/fixtures/results_absolute.html");
+        let base_url = Url::parse("http://localhost:8080/mirror/").unwrap();
+        let daily_urls = parse_daily_results(html, &base_url)
+            .unwrap()
+            .iter()
+            .map(|daily_url| daily_url.to_daily_url(&base_url))
+            .collect::<Vec<_>>();
+        insta::assert_debug_snapshot!(daily_urls, @r#"
+        [
+            "http://localhost:8080/mirror/en_gb/test-competition/results/club/task-9-on-2025-06-18/daily",
+            "http://localhost:8080/mirror/en_gb/test-competition/results/club/task-10-on-2025-06-19/daily",
+        ]
+        "#);
+    }
 }
@@ -0,0 +1,150 @@
+use crate::error::Error;
+use crate::output::{OutputFormat, TableRow, format_output};
+use crate::url_utils::DailyUrlInfo;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// A single IGC file that a run would download.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedFile {
+    pub competition: String,
+    pub class: String,
+    pub date: String,
+    pub task: String,
+    pub callsign: String,
+    pub pilot: String,
+    pub url: String,
+    /// Location the file would be saved to
+    pub path: String,
+    /// Whether the file is already there, so that it would be skipped or
+    /// only checked for updates
+    pub exists: bool,
+}
+
+impl PlannedFile {
+    pub fn new(
+        daily_info: &DailyUrlInfo,
+        callsign: &str,
+        pilot: &str,
+        url: &str,
+        path: &Path,
+    ) -> Self {
+        Self {
+            competition: daily_info.competition.clone(),
+            class: daily_info.class.clone(),
+            date: daily_info.date.to_string(),
+            task: daily_info.task_name.clone(),
+            callsign: callsign.to_string(),
+            pilot: pilot.to_string(),
+            url: url.to_string(),
+            path: path.display().to_string(),
+            exists: path.exists(),
+        }
+    }
+}
+
+impl TableRow for PlannedFile {
+    const HEADER: &'static [&'static str] = &[
+        "CLASS", "DATE", "TASK", "CALLSIGN", "PILOT", "PATH", "EXISTS",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        let exists = if self.exists { "yes" } else { "no" };
+        vec![
+            self.class.clone(),
+            self.date.clone(),
+            self.task.clone(),
+            self.callsign.clone(),
+            self.pilot.clone(),
+            self.path.clone(),
+            exists.to_string(),
+        ]
+    }
+}
+
+/// Formats the planned files as a table followed by the totals, or as JSON.
+pub fn format_plan(files: &[PlannedFile], format: OutputFormat) -> Result<String, Error> {
+    let output = format_output(files, format)?;
+    if format == OutputFormat::Json {
+        return Ok(output);
+    }
+
+    let classes = files
+        .iter()
+        .map(|file| &file.class)
+        .collect::<BTreeSet<_>>();
+    let days = files
+        .iter()
+        .map(|file| (&file.class, &file.date, &file.task))
+        .collect::<BTreeSet<_>>();
+    let existing = files.iter().filter(|file| file.exists).count();
+    Ok(format!(
+        "{output}\n{} IGC files on {} days in {} classes, {} already exist",
+        files.len(),
+        days.len(),
+        classes.len(),
+        existing
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned_file(class: &str, date: &str, callsign: &str, pilot: &str) -> PlannedFile {
+        PlannedFile {
+            competition: "test-competition".to_string(),
+            class: class.to_string(),
+            date: date.to_string(),
+            task: "task-1".to_string(),
+            callsign: callsign.to_string(),
+            pilot: pilot.to_string(),
+            url: format!("https://www.soaringspot.com/en_gb/download-contest-flight/{callsign}"),
+            path: format!("test-competition/{class}/{date}/{callsign}.igc"),
+            exists: callsign == "KK",
+        }
+    }
+
+    fn files() -> Vec<PlannedFile> {
+        vec![
+            planned_file("club", "2025-06-18", "KK", "Jiří Novák"),
+            planned_file("club", "2025-06-18", "LS", "John Smith"),
+            planned_file("standard", "2025-06-19", "A3", "Nora Reed"),
+        ]
+    }
+
+    #[test]
+    fn test_format_table() {
+        insta::assert_snapshot!(format_plan(&files(), OutputFormat::Table).unwrap(), @r#"
+        CLASS     DATE        TASK    CALLSIGN  PILOT       PATH                                         EXISTS
+        club      2025-06-18  task-1  KK        Jiří Novák  test-competition/club/2025-06-18/KK.igc      yes
+        club      2025-06-18  task-1  LS        John Smith  test-competition/club/2025-06-18/LS.igc      no
+        standard  2025-06-19  task-1  A3        Nora Reed   test-competition/standard/2025-06-19/A3.igc  no
+        3 IGC files on 2 days in 2 classes, 1 already exist
+        "#);
+        insta::assert_snapshot!(format_plan(&[], OutputFormat::Table).unwrap(), @r#"
+        CLASS  DATE  TASK  CALLSIGN  PILOT  PATH  EXISTS
+        0 IGC files on 0 days in 0 classes, 0 already exist
+        "#);
+    }
+
+    #[test]
+    fn test_format_json() {
+        insta::assert_snapshot!(format_plan(&files()[..1], OutputFormat::Json).unwrap(), @r#"
+        [
+          {
+            "competition": "test-competition",
+            "class": "club",
+            "date": "2025-06-18",
+            "task": "task-1",
+            "callsign": "KK",
+            "pilot": "Jiří Novák",
+            "url": "https://www.soaringspot.com/en_gb/download-contest-flight/KK",
+            "path": "test-competition/club/2025-06-18/KK.igc",
+            "exists": true
+          }
+        ]
+        "#);
+    }
+}
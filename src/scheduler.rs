@@ -0,0 +1,426 @@
+use crate::error::Error;
+use crate::url_utils::default_base_url;
+use jiff::Timestamp;
+use jiff::fmt::rfc2822::DateTimeParser;
+use reqwest::header::{
+    ETAG, HeaderMap, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER,
+};
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tokio::time::{Duration, Instant, sleep, sleep_until};
+use url::Url;
+
+/// Default number of requests per second sent to SoaringSpot.
+pub const DEFAULT_REQUESTS_PER_SECOND: f64 = 2.0;
+
+/// Longest time that requests are held back, either between two requests or
+/// because of a `Retry-After` header, so that a bogus value can't stall a
+/// run forever.
+pub const MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Marker that SoaringSpot includes in rate limited responses that are
+/// served with a success status.
+pub(crate) const TOO_MANY_REQUESTS_MARKER: &str = "<h1>Too Many Requests</h1>";
+
+/// Retry parameters for rate limited and transiently failing requests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries before giving up
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Exponential backoff multiplier applied to the delay after each retry
+    pub backoff_factor: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_delay: Duration::from_secs(1),
+            backoff_factor: 2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before retry number `attempt` (starting at 1).
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = self
+            .backoff_factor
+            .saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor)
+    }
+}
+
+/// `ETag` and `Last-Modified` values of a response, used to send
+/// conditional requests for the same URL later.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheValidators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let value = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: value(ETAG),
+            last_modified: value(LAST_MODIFIED),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// A page loaded by [Scheduler::get_page].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub text: String,
+    pub validators: CacheValidators,
+}
+
+/// Central scheduler for all HTTP requests sent to SoaringSpot.
+///
+/// All requests share a common requests-per-second budget, and when the
+/// server signals rate limiting (HTTP 429/503, optionally with a
+/// `Retry-After` header) all requests are paused, not only the one that
+/// was rejected.
+#[derive(Debug)]
+pub struct Scheduler {
+    client: reqwest::Client,
+    base_url: Url,
+    min_interval: Duration,
+    retry_policy: RetryPolicy,
+    next_slot: Mutex<Instant>,
+    paused_until: Mutex<Option<Instant>>,
+}
+
+impl Scheduler {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            base_url: default_base_url(),
+            min_interval: interval_for(DEFAULT_REQUESTS_PER_SECOND),
+            retry_policy: RetryPolicy::default(),
+            next_slot: Mutex::new(Instant::now()),
+            paused_until: Mutex::new(None),
+        }
+    }
+
+    /// Sets the maximum number of requests per second.
+    ///
+    /// A value of zero or less disables the request budget.
+    pub fn with_requests_per_second(mut self, requests_per_second: f64) -> Self {
+        self.min_interval = interval_for(requests_per_second);
+        self
+    }
+
+    /// Sets the retry policy for rate limited and failed requests.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the base URL of the site, to load the pages from a mirror or a
+    /// local test server instead of SoaringSpot.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Sends a GET request to `url` once the request budget allows it.
+    ///
+    /// Rate limited responses, with HTTP 429 status or HTTP 503 status and a
+    /// `Retry-After` header, pause all requests for the delay requested by
+    /// the header, or with exponential backoff, and are retried. Any other
+    /// response is returned to the caller as-is.
+    pub async fn get(&self, url: &str) -> Result<Response, Error> {
+        self.get_conditional(url, &CacheValidators::default()).await
+    }
+
+    /// Like [Scheduler::get], but sends `If-None-Match` and
+    /// `If-Modified-Since` headers for the given `validators`, so that the
+    /// server may respond with HTTP 304 Not Modified.
+    pub async fn get_conditional(
+        &self,
+        url: &str,
+        validators: &CacheValidators,
+    ) -> Result<Response, Error> {
+        let max_retries = self.retry_policy.max_retries;
+        let mut retry_count = 0;
+
+        loop {
+            self.wait_for_slot().await;
+
+            let mut request = self.client.get(url);
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+
+            let response = request.send().await?;
+            let requested_delay = retry_after(response.headers());
+            let rate_limited = match response.status() {
+                StatusCode::TOO_MANY_REQUESTS => true,
+                StatusCode::SERVICE_UNAVAILABLE => requested_delay.is_some(),
+                _ => false,
+            };
+            if !rate_limited {
+                return Ok(response);
+            }
+
+            if retry_count >= max_retries {
+                return Err(Error::RateLimited {
+                    url: url.to_string(),
+                    retries: max_retries,
+                });
+            }
+
+            retry_count += 1;
+            let delay = self.retry_policy.delay_for(retry_count);
+            self.pause_for(requested_delay.unwrap_or(delay));
+        }
+    }
+
+    /// Loads the page at `url` and returns its content.
+    ///
+    /// In addition to the rate limit handling of [Scheduler::get], network
+    /// errors, HTTP 5xx responses and "Too Many Requests" pages are retried
+    /// according to the [RetryPolicy].
+    pub async fn get_text(&self, url: &str) -> Result<String, Error> {
+        match self.get_page(url, &CacheValidators::default()).await? {
+            Some(page) => Ok(page.text),
+            None => Err(Error::HttpStatus {
+                status: StatusCode::NOT_MODIFIED,
+                url: url.to_string(),
+            }),
+        }
+    }
+
+    /// Like [Scheduler::get_text], but sends a conditional request for the
+    /// given `validators` and returns `None` if the page has not been
+    /// modified since.
+    pub async fn get_page(
+        &self,
+        url: &str,
+        validators: &CacheValidators,
+    ) -> Result<Option<Page>, Error> {
+        let max_retries = self.retry_policy.max_retries;
+        let mut retry_count = 0;
+
+        loop {
+            let (error, rate_limited) = match self.try_get_page(url, validators).await {
+                Ok(Fetched::Page(page)) => return Ok(Some(page)),
+                Ok(Fetched::NotModified) => return Ok(None),
+                Ok(Fetched::TooManyRequests) => {
+                    let error = Error::RateLimited {
+                        url: url.to_string(),
+                        retries: max_retries,
+                    };
+                    (error, true)
+                }
+                Err(error) if error.is_transient() => (error, false),
+                Err(error) => return Err(error),
+            };
+
+            if retry_count >= max_retries {
+                return Err(error);
+            }
+
+            retry_count += 1;
+            self.back_off(retry_count, rate_limited).await;
+        }
+    }
+
+    /// Waits before retry number `attempt` of a failed request, according to
+    /// the [RetryPolicy]. Rate limiting affects all requests, so they are all
+    /// paused, while a server or network error only delays this one.
+    pub(crate) async fn back_off(&self, attempt: u32, rate_limited: bool) {
+        let delay = self.retry_policy.delay_for(attempt);
+        if rate_limited {
+            self.pause_for(delay);
+        } else {
+            sleep(delay.min(MAX_DELAY)).await;
+        }
+    }
+
+    /// Loads the page at `url` once.
+    async fn try_get_page(
+        &self,
+        url: &str,
+        validators: &CacheValidators,
+    ) -> Result<Fetched, Error> {
+        let response = self.get_conditional(url, validators).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Fetched::NotModified);
+        }
+        if !response.status().is_success() {
+            return Err(Error::HttpStatus {
+                status: response.status(),
+                url: url.to_string(),
+            });
+        }
+
+        let validators = CacheValidators::from_headers(response.headers());
+        let text = response.text().await?;
+        if text.contains(TOO_MANY_REQUESTS_MARKER) {
+            return Ok(Fetched::TooManyRequests);
+        }
+
+        Ok(Fetched::Page(Page { text, validators }))
+    }
+
+    /// Pauses all requests for at least `duration` from now, but at most
+    /// for [MAX_DELAY].
+    pub fn pause_for(&self, duration: Duration) {
+        let Some(until) = Instant::now().checked_add(duration.min(MAX_DELAY)) else {
+            return;
+        };
+        let mut paused_until = self.paused_until.lock().unwrap();
+        if paused_until.is_none_or(|current| current < until) {
+            *paused_until = Some(until);
+        }
+    }
+
+    /// Waits until any active pause has passed and the request budget
+    /// allows sending the next request.
+    async fn wait_for_slot(&self) {
+        loop {
+            let paused_until = *self.paused_until.lock().unwrap();
+            match paused_until {
+                Some(until) if until > Instant::now() => sleep_until(until).await,
+                _ => break,
+            }
+        }
+
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.min_interval;
+            slot
+        };
+
+        sleep_until(slot).await;
+    }
+}
+
+/// Outcome of a single page request.
+enum Fetched {
+    Page(Page),
+    NotModified,
+    /// The page is a "Too Many Requests" page served with a success status
+    TooManyRequests,
+}
+
+/// Returns the time between two requests, at most [MAX_DELAY].
+fn interval_for(requests_per_second: f64) -> Duration {
+    if requests_per_second > 0.0 {
+        Duration::try_from_secs_f64(1.0 / requests_per_second)
+            .map_or(MAX_DELAY, |interval| interval.min(MAX_DELAY))
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// Parses the `Retry-After` header, which is either a number of seconds or
+/// an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTimeParser::new().parse_timestamp(value).ok()?;
+    let delay = date.duration_since(Timestamp::now());
+    Some(Duration::try_from(delay).unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        // Dates in the past mean "retry now"
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("soon"));
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_cache_validators_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(CacheValidators::from_headers(&headers).is_empty());
+
+        headers.insert(ETAG, HeaderValue::from_static("\"5039-1\""));
+        headers.insert(
+            LAST_MODIFIED,
+            HeaderValue::from_static("Thu, 19 Jun 2025 18:30:00 GMT"),
+        );
+        assert_eq!(
+            CacheValidators::from_headers(&headers),
+            CacheValidators {
+                etag: Some("\"5039-1\"".to_string()),
+                last_modified: Some("Thu, 19 Jun 2025 18:30:00 GMT".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_retry_policy_delay_for() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay_for(1), Duration::from_secs(1));
+        assert_eq!(policy.delay_for(2), Duration::from_secs(2));
+        assert_eq!(policy.delay_for(5), Duration::from_secs(16));
+    }
+
+    #[test]
+    fn test_interval_for() {
+        assert_eq!(interval_for(2.0), Duration::from_millis(500));
+        assert_eq!(interval_for(0.5), Duration::from_secs(2));
+        assert_eq!(interval_for(0.0), Duration::ZERO);
+        assert_eq!(interval_for(1e-300), MAX_DELAY);
+        assert_eq!(interval_for(f64::MIN_POSITIVE), MAX_DELAY);
+    }
+
+    #[test]
+    fn test_pause_for_is_capped() {
+        let scheduler = Scheduler::new(reqwest::Client::new());
+        scheduler.pause_for(Duration::MAX);
+        let paused_until = scheduler.paused_until.lock().unwrap().unwrap();
+        assert!(paused_until <= Instant::now() + MAX_DELAY);
+    }
+}
@@ -0,0 +1,349 @@
+use std::time::Duration;
+
+/// A task definition as published on the SoaringSpot task page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub kind: TaskKind,
+    /// Task distance in km
+    pub distance: Option<f64>,
+    /// Minimum task time, for assigned area tasks
+    pub min_time: Option<Duration>,
+    /// Free-text task information, e.g. scoring parameters
+    pub info: Option<String>,
+    /// Start, turnpoints and finish in task order
+    pub turnpoints: Vec<Turnpoint>,
+}
+
+impl Task {
+    pub fn start(&self) -> Option<&Turnpoint> {
+        self.turnpoints.first()
+    }
+
+    pub fn finish(&self) -> Option<&Turnpoint> {
+        self.turnpoints.last()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskKind {
+    Racing,
+    AssignedArea,
+    Other(String),
+}
+
+impl TaskKind {
+    /// Derives the task kind from the task info text, which starts with
+    /// e.g. "Racing Task" or "Assigned Area Task", in English or one of the
+    /// other languages SoaringSpot is commonly used in.
+    pub fn from_info(info: &str) -> Self {
+        let name = info.split([',', ';']).next().unwrap_or_default().trim();
+        match name.to_lowercase().as_str() {
+            "racing task"
+            | "racing"
+            | "rt"
+            | "geschwindigkeitsaufgabe"
+            | "rennaufgabe"
+            | "rychlostní úloha"
+            | "épreuve de vitesse"
+            | "course de vitesse"
+            | "prova di velocità"
+            | "tarea de velocidad"
+            | "prueba de velocidad"
+            | "prova de velocidade"
+            | "snelheidstaak"
+            | "konkurencja na prędkość" => TaskKind::Racing,
+            "assigned area task"
+            | "aat"
+            | "area task"
+            | "gebietsaufgabe"
+            | "úloha s přidělenými oblastmi"
+            | "épreuve de zones"
+            | "prova ad aree assegnate"
+            | "tarea de área asignada"
+            | "prova de área designada"
+            | "gebiedstaak"
+            | "konkurencja obszarowa" => TaskKind::AssignedArea,
+            _ => TaskKind::Other(name.to_string()),
+        }
+    }
+}
+
+/// Extracts the minimum task time from the task info text, e.g.
+/// "Assigned Area Task, Task time: 3:00, ...".
+pub(crate) fn min_time_from_info(info: &str) -> Option<Duration> {
+    info.split([',', ';']).find_map(|part| {
+        let (label, value) = part.split_once([':', '='])?;
+        let label = label.trim().to_lowercase();
+        if !matches!(label.as_str(), "task time" | "minimum time" | "min time") {
+            return None;
+        }
+
+        let mut parts = value.trim().split(':').map(|part| part.parse::<u64>().ok());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Some(hours)), Some(Some(minutes)), None, None) => {
+                Some(Duration::from_secs(hours * 3600 + minutes * 60))
+            }
+            (Some(Some(hours)), Some(Some(minutes)), Some(Some(seconds)), None) => {
+                Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// A single point of a task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turnpoint {
+    pub kind: TurnpointKind,
+    pub name: String,
+    /// Latitude in decimal degrees, positive north
+    pub latitude: f64,
+    /// Longitude in decimal degrees, positive east
+    pub longitude: f64,
+    pub observation_zone: ObservationZone,
+    /// Length of the leg from the previous point in km
+    pub leg_distance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TurnpointKind {
+    Start,
+    Turnpoint,
+    Finish,
+}
+
+impl TurnpointKind {
+    /// Parses the type cell of the task turnpoints table, e.g. "Start",
+    /// "Turnpoint" or "Finish", in English or one of the other languages
+    /// SoaringSpot is commonly used in.
+    pub fn from_type(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "start" | "abflug" | "départ" | "partenza" | "salida" | "partida" | "vertrek" => {
+                Some(TurnpointKind::Start)
+            }
+            "turnpoint" | "turn point" | "tp" | "wendepunkt" | "otočný bod" | "point de virage"
+            | "pilone" | "punto di virata" | "baliza" | "punto de viraje" | "ponto de virada"
+            | "keerpunt" | "punkt zwrotny" => Some(TurnpointKind::Turnpoint),
+            "finish" | "ziel" | "cíl" | "arrivée" | "arrivo" | "llegada" | "meta" | "chegada"
+            | "aankomst" => Some(TurnpointKind::Finish),
+            _ => None,
+        }
+    }
+}
+
+/// Observation zone of a turnpoint. All lengths are in meters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObservationZone {
+    Line {
+        length: f64,
+    },
+    Cylinder {
+        radius: f64,
+    },
+    Sector {
+        angle: f64,
+        radius: f64,
+    },
+    /// Observation zone description that could not be interpreted
+    Other(String),
+}
+
+impl ObservationZone {
+    /// Parses descriptions like "Line 10.00 km", "Cylinder 500 m" or
+    /// "Sector 90° 3.00 km".
+    pub fn parse(text: &str) -> Self {
+        let other = || ObservationZone::Other(text.to_string());
+
+        let mut words = text.split_whitespace();
+        let Some(kind) = words.next() else {
+            return other();
+        };
+        let rest = words.collect::<Vec<_>>();
+
+        match (kind.to_lowercase().as_str(), rest.as_slice()) {
+            (
+                "line" | "linie" | "čára" | "ligne" | "linea" | "línea" | "lijn" | "linia",
+                [length, unit],
+            ) => match parse_length(length, unit) {
+                Some(length) => ObservationZone::Line { length },
+                None => other(),
+            },
+            (
+                "cylinder" | "zylinder" | "válec" | "cylindre" | "cilindro" | "cilinder",
+                [radius, unit],
+            ) => match parse_length(radius, unit) {
+                Some(radius) => ObservationZone::Cylinder { radius },
+                None => other(),
+            },
+            ("sector" | "sektor" | "secteur" | "settore", [angle, radius, unit]) => {
+                let angle = angle.trim_end_matches('°').replace(',', ".").parse().ok();
+                match (angle, parse_length(radius, unit)) {
+                    (Some(angle), Some(radius)) => ObservationZone::Sector { angle, radius },
+                    _ => other(),
+                }
+            }
+            _ => other(),
+        }
+    }
+}
+
+/// Parses a length with a "km" or "m" unit into meters, accepting a decimal
+/// comma as well.
+fn parse_length(value: &str, unit: &str) -> Option<f64> {
+    let value = value.replace(',', ".").parse::<f64>().ok()?;
+    match unit {
+        "km" => Some(value * 1000.),
+        "m" => Some(value),
+        _ => None,
+    }
+}
+
+/// Parses coordinates like `49°19.567'N` into decimal degrees.
+///
+/// Plain decimal degrees like `49.32611` are accepted as well.
+pub fn parse_coordinate(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if let Ok(value) = text.parse() {
+        return Some(value);
+    }
+
+    let hemisphere = text.chars().last()?;
+    let sign = match hemisphere.to_ascii_uppercase() {
+        'N' | 'E' => 1.,
+        'S' | 'W' => -1.,
+        _ => return None,
+    };
+
+    let text = &text[..text.len() - hemisphere.len_utf8()];
+    let (degrees, minutes) = text.split_once('°')?;
+    let degrees = degrees.trim().parse::<f64>().ok()?;
+    let minutes = minutes
+        .trim()
+        .trim_end_matches(['\'', '′'])
+        .parse::<f64>()
+        .ok()?;
+    if minutes >= 60. {
+        return None;
+    }
+
+    Some(sign * (degrees + minutes / 60.))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_coordinate() {
+        let latitude = parse_coordinate("49°19.567'N").unwrap();
+        assert!((latitude - 49.326117).abs() < 1e-6);
+
+        let longitude = parse_coordinate("014°39.083'E").unwrap();
+        assert!((longitude - 14.651383).abs() < 1e-6);
+
+        assert_eq!(parse_coordinate("33°30.000'S"), Some(-33.5));
+        assert_eq!(parse_coordinate("070°45.000'W"), Some(-70.75));
+        assert_eq!(parse_coordinate("49.5"), Some(49.5));
+
+        assert_eq!(parse_coordinate("49°19.567'X"), None);
+        assert_eq!(parse_coordinate("49°75.000'N"), None);
+        assert_eq!(parse_coordinate(""), None);
+    }
+
+    #[test]
+    fn test_parse_observation_zone() {
+        assert_eq!(
+            ObservationZone::parse("Line 10.00 km"),
+            ObservationZone::Line { length: 10000. }
+        );
+        assert_eq!(
+            ObservationZone::parse("Cylinder 500 m"),
+            ObservationZone::Cylinder { radius: 500. }
+        );
+        assert_eq!(
+            ObservationZone::parse("Sector 90° 3.00 km"),
+            ObservationZone::Sector {
+                angle: 90.,
+                radius: 3000.
+            }
+        );
+        assert_eq!(
+            ObservationZone::parse("Zylinder 0,50 km"),
+            ObservationZone::Cylinder { radius: 500. }
+        );
+        assert_eq!(
+            ObservationZone::parse("Keyhole"),
+            ObservationZone::Other("Keyhole".to_string())
+        );
+    }
+
+    #[test]
+    fn test_min_time_from_info() {
+        assert_eq!(
+            min_time_from_info("Assigned Area Task, Task time: 3:00, Maximum Points: 1000"),
+            Some(Duration::from_secs(3 * 3600))
+        );
+        assert_eq!(
+            min_time_from_info("Assigned Area Task; Minimum time = 2:30:00"),
+            Some(Duration::from_secs(2 * 3600 + 30 * 60))
+        );
+        assert_eq!(min_time_from_info("Racing Task, Max speed pts: 455"), None);
+    }
+
+    #[test]
+    fn test_task_kind_from_info() {
+        assert_eq!(
+            TaskKind::from_info("Racing Task, Maximum Points: 1000"),
+            TaskKind::Racing
+        );
+        assert_eq!(
+            TaskKind::from_info("Assigned Area Task, Task time: 3:00"),
+            TaskKind::AssignedArea
+        );
+        assert_eq!(
+            TaskKind::from_info("Distance Handicapped Task"),
+            TaskKind::Other("Distance Handicapped Task".to_string())
+        );
+
+        // Localised pages
+        assert_eq!(
+            TaskKind::from_info("Rychlostní úloha, Maximum bodů: 1000"),
+            TaskKind::Racing
+        );
+        assert_eq!(
+            TaskKind::from_info("Gebietsaufgabe, Aufgabenzeit: 3:00"),
+            TaskKind::AssignedArea
+        );
+    }
+
+    #[test]
+    fn test_turnpoint_kind_from_type() {
+        assert_eq!(
+            TurnpointKind::from_type("Start"),
+            Some(TurnpointKind::Start)
+        );
+        assert_eq!(
+            TurnpointKind::from_type("Turnpoint"),
+            Some(TurnpointKind::Turnpoint)
+        );
+        assert_eq!(
+            TurnpointKind::from_type(" Finish "),
+            Some(TurnpointKind::Finish)
+        );
+
+        // Localised pages
+        assert_eq!(
+            TurnpointKind::from_type("Abflug"),
+            Some(TurnpointKind::Start)
+        );
+        assert_eq!(
+            TurnpointKind::from_type("Otočný bod"),
+            Some(TurnpointKind::Turnpoint)
+        );
+        assert_eq!(TurnpointKind::from_type("Cíl"), Some(TurnpointKind::Finish));
+
+        // Results headers are no turnpoint types
+        assert_eq!(TurnpointKind::from_type("Speed"), None);
+        assert_eq!(TurnpointKind::from_type(""), None);
+    }
+}
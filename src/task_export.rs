@@ -0,0 +1,225 @@
+use crate::error::Error;
+use crate::task::{ObservationZone, Task, TaskKind, Turnpoint, TurnpointKind};
+use html_escape::encode_double_quoted_attribute;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Serializes `task` as an XCSoar `.tsk` XML task file.
+pub fn to_xcsoar_task(task: &Task) -> String {
+    let task_type = match task.kind {
+        TaskKind::AssignedArea => "AAT",
+        TaskKind::Racing | TaskKind::Other(_) => "RT",
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write!(xml, "<Task type=\"{task_type}\"").unwrap();
+    if let Some(min_time) = task.min_time {
+        write!(xml, " aat_min_time=\"{}\"", min_time.as_secs()).unwrap();
+    }
+    xml.push_str(">\n");
+
+    for (id, turnpoint) in task.turnpoints.iter().enumerate() {
+        let point_type = match (turnpoint.kind, &task.kind) {
+            (TurnpointKind::Start, _) => "Start",
+            (TurnpointKind::Finish, _) => "Finish",
+            (TurnpointKind::Turnpoint, TaskKind::AssignedArea) => "Area",
+            (TurnpointKind::Turnpoint, _) => "Turn",
+        };
+        let name = encode_double_quoted_attribute(&turnpoint.name);
+
+        writeln!(xml, "  <Point type=\"{point_type}\">").unwrap();
+        writeln!(
+            xml,
+            "    <Waypoint name=\"{name}\" id=\"{id}\" comment=\"\" altitude=\"0\">"
+        )
+        .unwrap();
+        writeln!(
+            xml,
+            "      <Location latitude=\"{:.6}\" longitude=\"{:.6}\"/>",
+            turnpoint.latitude, turnpoint.longitude
+        )
+        .unwrap();
+        xml.push_str("    </Waypoint>\n");
+
+        // Leave unknown zones out, so that XCSoar applies its defaults
+        match turnpoint.observation_zone {
+            ObservationZone::Line { length } => {
+                writeln!(xml, "    <ObservationZone type=\"Line\" length=\"{length}\"/>")
+            }
+            ObservationZone::Cylinder { radius } => {
+                writeln!(xml, "    <ObservationZone type=\"Cylinder\" radius=\"{radius}\"/>")
+            }
+            ObservationZone::Sector { angle, radius } => writeln!(
+                xml,
+                "    <ObservationZone type=\"SymmetricQuadrant\" radius=\"{radius}\" angle=\"{angle}\"/>"
+            ),
+            ObservationZone::Other(_) => Ok(()),
+        }
+        .unwrap();
+
+        xml.push_str("  </Point>\n");
+    }
+
+    xml.push_str("</Task>\n");
+    xml
+}
+
+/// Serializes `task` as a SeeYou `.cup` file, with one waypoint per
+/// turnpoint and a task section named `name`.
+pub fn to_seeyou_cup(task: &Task, name: &str) -> String {
+    let mut cup = String::from("name,code,country,lat,lon,elev,style,rwdir,rwlen,freq,desc\n");
+    for turnpoint in &task.turnpoints {
+        let name = quote(&turnpoint.name);
+        writeln!(
+            cup,
+            "{name},{name},,{},{},0m,1,,,,",
+            format_cup_coordinate(turnpoint.latitude, 2, ['N', 'S']),
+            format_cup_coordinate(turnpoint.longitude, 3, ['E', 'W'])
+        )
+        .unwrap();
+    }
+
+    // The task line starts with the takeoff and ends with the landing point,
+    // which SeeYou marks as unknown with "???"
+    cup.push_str("-----Related Tasks-----\n");
+    cup.push_str(&quote(name));
+    cup.push_str(",\"???\"");
+    for turnpoint in &task.turnpoints {
+        cup.push(',');
+        cup.push_str(&quote(&turnpoint.name));
+    }
+    cup.push_str(",\"???\"\n");
+
+    if let Some(min_time) = task.min_time {
+        let seconds = min_time.as_secs();
+        writeln!(
+            cup,
+            "Options,TaskTime={:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+        .unwrap();
+    }
+
+    for (index, turnpoint) in task.turnpoints.iter().enumerate() {
+        if let Some(zone) = cup_observation_zone(turnpoint) {
+            writeln!(cup, "ObsZone={index},{zone}").unwrap();
+        }
+    }
+
+    cup
+}
+
+/// Writes the XCSoar and SeeYou task files for `task` to `target_dir` and
+/// returns their paths.
+pub async fn write_task_files(
+    target_dir: &Path,
+    name: &str,
+    task: &Task,
+) -> Result<Vec<PathBuf>, Error> {
+    let tsk_path = target_dir.join("task.tsk");
+    fs::write(&tsk_path, to_xcsoar_task(task)).await?;
+
+    let cup_path = target_dir.join("task.cup");
+    fs::write(&cup_path, to_seeyou_cup(task, name)).await?;
+
+    Ok(vec![tsk_path, cup_path])
+}
+
+/// Quotes a CUP text field, doubling any quotes inside it.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// Formats a coordinate like `4919.567N` or `01439.083E`.
+fn format_cup_coordinate(value: f64, degree_digits: usize, hemispheres: [char; 2]) -> String {
+    let hemisphere = if value < 0. {
+        hemispheres[1]
+    } else {
+        hemispheres[0]
+    };
+
+    // Round to thousandths of a minute first, so that 59.9999' carries over
+    let thousandths = (value.abs() * 60_000.).round() as u64;
+    let degrees = thousandths / 60_000;
+    let minutes = thousandths % 60_000;
+    format!(
+        "{degrees:0degree_digits$}{:02}.{:03}{hemisphere}",
+        minutes / 1000,
+        minutes % 1000
+    )
+}
+
+/// Returns the CUP observation zone options for a turnpoint.
+///
+/// Lines and sectors are oriented relative to the neighbouring legs: the
+/// start towards the next point, the finish towards the previous point and
+/// turnpoints symmetrically.
+fn cup_observation_zone(turnpoint: &Turnpoint) -> Option<String> {
+    let style = match turnpoint.kind {
+        TurnpointKind::Start => 2,
+        TurnpointKind::Turnpoint => 1,
+        TurnpointKind::Finish => 3,
+    };
+
+    match turnpoint.observation_zone {
+        // R1 is half the length of the line
+        ObservationZone::Line { length } => {
+            Some(format!("Style={style},R1={}m,A1=180,Line=1", length / 2.))
+        }
+        ObservationZone::Cylinder { radius } => Some(format!("Style={style},R1={radius}m,A1=180")),
+        // A1 is half the opening angle of the sector
+        ObservationZone::Sector { angle, radius } => {
+            Some(format!("Style={style},R1={radius}m,A1={}", angle / 2.))
+        }
+        ObservationZone::Other(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_task;
+    use std::time::Duration;
+
+    fn task() -> Task {
+        let html = include_str!("../tests/fixtures/task.html");
+        parse_task(html).unwrap()
+    }
+
+    #[test]
+    fn test_format_cup_coordinate() {
+        assert_eq!(format_cup_coordinate(49.326117, 2, ['N', 'S']), "4919.567N");
+        assert_eq!(format_cup_coordinate(-70.75, 3, ['E', 'W']), "07045.000W");
+        assert_eq!(
+            format_cup_coordinate(14.9999999, 3, ['E', 'W']),
+            "01500.000E"
+        );
+    }
+
+    #[test]
+    fn test_to_xcsoar_task() {
+        insta::assert_snapshot!(to_xcsoar_task(&task()));
+    }
+
+    #[test]
+    fn test_to_seeyou_cup() {
+        insta::assert_snapshot!(to_seeyou_cup(&task(), "task-10 2025-06-19"));
+    }
+
+    #[test]
+    fn test_assigned_area_task() {
+        let mut task = task();
+        task.kind = TaskKind::AssignedArea;
+        task.min_time = Some(Duration::from_secs(3 * 3600 + 30 * 60));
+
+        let xml = to_xcsoar_task(&task);
+        assert!(xml.contains("<Task type=\"AAT\" aat_min_time=\"12600\">"));
+        assert!(xml.contains("<Point type=\"Area\">"));
+
+        let cup = to_seeyou_cup(&task, "task-10");
+        assert!(cup.contains("\nOptions,TaskTime=03:30:00\n"));
+    }
+}
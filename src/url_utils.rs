@@ -1,8 +1,42 @@
+use crate::error::Error;
 use jiff::civil::Date;
-use url::Url;
+use url::{Position, Url};
 
-#[derive(Debug, Clone)]
+/// Language of the SoaringSpot pages if a URL does not specify one.
+pub const DEFAULT_LANGUAGE: &str = "en_gb";
+
+/// Base URL of the SoaringSpot site.
+pub const DEFAULT_BASE_URL: &str = "https://www.soaringspot.com/";
+
+/// Returns [DEFAULT_BASE_URL] as a parsed URL.
+pub fn default_base_url() -> Url {
+    Url::parse(DEFAULT_BASE_URL).expect("Invalid default base URL")
+}
+
+/// Resolves a link found on a page, like `/en_gb/{competition}/results`,
+/// against `base_url`.
+///
+/// Unlike [Url::join], any path of the base URL is kept, so that a mirror
+/// served below a path prefix works as well. Absolute links to SoaringSpot
+/// are moved to `base_url` too, links to other sites are kept.
+pub fn resolve_url(base_url: &Url, href: &str) -> String {
+    let url = Url::parse(href);
+    let href = match &url {
+        Ok(url) if is_same_host(url, &default_base_url()) => &url[Position::BeforePath..],
+        Ok(_) => return href.to_string(),
+        Err(_) => href,
+    };
+    format!(
+        "{}/{}",
+        base_url.as_str().trim_end_matches('/'),
+        href.trim_start_matches('/')
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DailyUrlInfo {
+    /// Language prefix of the URL, e.g. "en_gb" or "cs"
+    pub language: String,
     pub competition: String,
     pub class: String,
     pub date: Date,
@@ -15,12 +49,23 @@ impl DailyUrlInfo {
     }
 
     /// Generates a daily result URL from the DailyUrlInfo
-    pub fn to_daily_url(&self) -> String {
+    pub fn to_daily_url(&self, base_url: &Url) -> String {
         let date_str = self.date.strftime("%Y-%m-%d").to_string();
-        format!(
-            "https://www.soaringspot.com/en_gb/{}/results/{}/{}-on-{}/daily",
-            self.competition, self.class, self.task_name, date_str
-        )
+        let path = format!(
+            "{}/{}/results/{}/{}-on-{}/daily",
+            self.language, self.competition, self.class, self.task_name, date_str
+        );
+        resolve_url(base_url, &path)
+    }
+
+    /// Generates the task page URL from the DailyUrlInfo
+    pub fn to_task_url(&self, base_url: &Url) -> String {
+        let date_str = self.date.strftime("%Y-%m-%d").to_string();
+        let path = format!(
+            "{}/{}/tasks/{}/{}-on-{}",
+            self.language, self.competition, self.class, self.task_name, date_str
+        );
+        resolve_url(base_url, &path)
     }
 }
 
@@ -29,76 +74,155 @@ pub enum UrlInfo {
     /// Daily results - has competition, class, and date
     Daily(DailyUrlInfo),
     /// Class results - has competition and class, needs to discover all dates
-    Class { competition: String, class: String },
+    Class {
+        language: String,
+        competition: String,
+        class: String,
+    },
     /// All competition results - has competition only, needs to discover all classes and dates
-    Competition { competition: String },
+    Competition {
+        language: String,
+        competition: String,
+    },
 }
 
-pub fn extract_url_info(url: &Url) -> Result<UrlInfo, Box<dyn std::error::Error>> {
+/// Returns the URL of the results overview page of a competition.
+pub fn results_url(base_url: &Url, language: &str, competition: &str) -> String {
+    resolve_url(base_url, &format!("{language}/{competition}/results"))
+}
+
+/// Checks whether `url` is on the same host and port as `base_url`, with or
+/// without a "www." prefix.
+fn is_same_host(url: &Url, base_url: &Url) -> bool {
+    let host = |url: &Url| {
+        url.host_str()
+            .map(|host| host.trim_start_matches("www.").to_string())
+    };
+    host(url) == host(base_url) && url.port() == base_url.port()
+}
+
+/// Checks whether a path segment looks like a language prefix like "en_gb",
+/// "en", "cs" or "pt_br".
+fn is_language(segment: &str) -> bool {
+    let is_code = |code: &str| code.len() == 2 && code.bytes().all(|b| b.is_ascii_lowercase());
+    match segment.split_once('_') {
+        Some((language, region)) => is_code(language) && is_code(region),
+        None => is_code(segment),
+    }
+}
+
+/// Extracts the competition, class and day that a URL below `base_url`
+/// points to.
+pub fn extract_url_info(url: &Url, base_url: &Url) -> Result<UrlInfo, Error> {
     // Validate the URL scheme
     if url.scheme() != "https" && url.scheme() != "http" {
-        return Err("URL must use HTTP or HTTPS scheme".into());
+        return Err(Error::InvalidUrl(
+            "URL must use HTTP or HTTPS scheme".to_string(),
+        ));
     }
 
     // Validate the host
-    let host = url.host_str().ok_or("Invalid URL - missing host")?;
-    if host != "www.soaringspot.com" && host != "soaringspot.com" {
-        return Err("URL must be from soaringspot.com or www.soaringspot.com".into());
+    let host = url
+        .host_str()
+        .ok_or_else(|| Error::InvalidUrl("Invalid URL - missing host".to_string()))?;
+    if !is_same_host(url, base_url) {
+        return Err(Error::UnsupportedHost {
+            host: host.to_string(),
+            base_url: base_url.to_string(),
+        });
     }
 
     let mut segments = url
         .path_segments()
-        .ok_or("Invalid URL path")?
-        .filter(|s| !s.is_empty());
+        .ok_or_else(|| Error::InvalidUrl("Invalid URL path".to_string()))?
+        .filter(|s| !s.is_empty())
+        .peekable();
+
+    // Skip the path of the base URL, e.g. for a mirror below a path prefix
+    for base_segment in base_url.path_segments().into_iter().flatten() {
+        if base_segment.is_empty() {
+            continue;
+        }
+        if segments.next() != Some(base_segment) {
+            return Err(Error::InvalidUrl(format!(
+                "URL must start with the base URL {base_url}"
+            )));
+        }
+    }
 
-    // Get language code
-    let _language = segments
-        .next()
-        .ok_or("Invalid URL format - missing path segments")?;
+    // Get language code, URLs without one are served in the default language.
+    // The language is followed by the competition, while the competition is
+    // followed by "results", so two-letter competitions are told apart.
+    let mut lookahead = segments.clone();
+    let first_segment = lookahead.next().ok_or_else(|| {
+        Error::InvalidUrl("Invalid URL format - missing path segments".to_string())
+    })?;
+    let has_language = match lookahead.next() {
+        Some(second_segment) => second_segment != "results",
+        None => is_language(first_segment),
+    };
+    let language = if has_language {
+        segments.next().unwrap_or_default().to_string()
+    } else {
+        DEFAULT_LANGUAGE.to_string()
+    };
 
     // Get competition name
     let competition = segments
         .next()
-        .ok_or("URL must contain competition name")?
+        .ok_or_else(|| Error::InvalidUrl("URL must contain competition name".to_string()))?
         .to_string();
 
     // Pattern: /en_gb/{competition}
     let Some(third_segment) = segments.next() else {
-        return Ok(UrlInfo::Competition { competition });
+        return Ok(UrlInfo::Competition {
+            language,
+            competition,
+        });
     };
 
     // Must be a results URL from here
     if third_segment != "results" {
-        return Err("Unsupported URL format".into());
+        return Err(Error::InvalidUrl("Unsupported URL format".to_string()));
     }
 
     // Pattern: /en_gb/{competition}/results
     let Some(class) = segments.next() else {
-        return Ok(UrlInfo::Competition { competition });
+        return Ok(UrlInfo::Competition {
+            language,
+            competition,
+        });
     };
 
     let class = class.to_string();
 
     // Pattern: /en_gb/{competition}/results/{class}
     let Some(task) = segments.next() else {
-        return Ok(UrlInfo::Class { competition, class });
+        return Ok(UrlInfo::Class {
+            language,
+            competition,
+            class,
+        });
     };
 
     // Pattern: /en_gb/{competition}/results/{class}/{task-name}-on-{date}(/daily)?
     if !task.contains("-on-") {
-        return Err("Unsupported URL format".into());
+        return Err(Error::InvalidUrl("Unsupported URL format".to_string()));
     }
 
     // Extract the task name and date from {task-name}-on-{date}
     let (task_name, date_str) = task
         .split_once("-on-")
-        .ok_or("Could not extract date from task segment")?;
+        .ok_or_else(|| Error::InvalidUrl("Could not extract date from task segment".to_string()))?;
 
     // Parse the date string using jiff
-    let date = Date::strptime("%Y-%m-%d", date_str)
-        .map_err(|e| format!("Failed to parse date '{}': {}", date_str, e))?;
+    let date = Date::strptime("%Y-%m-%d", date_str).map_err(|source| Error::InvalidDate {
+        value: date_str.to_string(),
+        source,
+    })?;
 
     Ok(UrlInfo::Daily(DailyUrlInfo {
+        language,
         competition,
         class,
         date,
@@ -115,10 +239,11 @@ mod tests {
         // Test valid daily results URL
         let url = "https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/task-10-on-2025-06-19/daily";
         let url = Url::parse(url).unwrap();
-        let info = extract_url_info(&url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
         insta::assert_debug_snapshot!(info, @r#"
         Daily(
             DailyUrlInfo {
+                language: "en_gb",
                 competition: "39th-fai-world-gliding-championships-tabor-2025",
                 class: "club",
                 date: 2025-06-19,
@@ -130,10 +255,11 @@ mod tests {
         // Test valid daily results URL with trailing slash
         let url = "https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/task-10-on-2025-06-19/daily/";
         let url = Url::parse(url).unwrap();
-        let info = extract_url_info(&url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
         insta::assert_debug_snapshot!(info, @r#"
         Daily(
             DailyUrlInfo {
+                language: "en_gb",
                 competition: "39th-fai-world-gliding-championships-tabor-2025",
                 class: "club",
                 date: 2025-06-19,
@@ -145,10 +271,11 @@ mod tests {
         // Test different class
         let url = "https://www.soaringspot.com/en_gb/competition/results/standard/task-5-on-2024-07-15/daily";
         let url = Url::parse(url).unwrap();
-        let info = extract_url_info(&url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
         insta::assert_debug_snapshot!(info, @r#"
         Daily(
             DailyUrlInfo {
+                language: "en_gb",
                 competition: "competition",
                 class: "standard",
                 date: 2024-07-15,
@@ -160,9 +287,10 @@ mod tests {
         // Test competition URL
         let url = "https://www.soaringspot.com/en_gb/test";
         let url = Url::parse(url).unwrap();
-        let info = extract_url_info(&url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
         insta::assert_debug_snapshot!(info, @r#"
         Competition {
+            language: "en_gb",
             competition: "test",
         }
         "#);
@@ -170,9 +298,10 @@ mod tests {
         // Test competition URL with trailing slash
         let url = "https://www.soaringspot.com/en_gb/test/";
         let url = Url::parse(url).unwrap();
-        let info = extract_url_info(&url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
         insta::assert_debug_snapshot!(info, @r#"
         Competition {
+            language: "en_gb",
             competition: "test",
         }
         "#);
@@ -180,9 +309,10 @@ mod tests {
         // Test class URL
         let url = "https://www.soaringspot.com/en_gb/test/results/club";
         let url = Url::parse(url).unwrap();
-        let info = extract_url_info(&url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
         insta::assert_debug_snapshot!(info, @r#"
         Class {
+            language: "en_gb",
             competition: "test",
             class: "club",
         }
@@ -191,9 +321,10 @@ mod tests {
         // Test all competition results URL
         let url = "https://www.soaringspot.com/en_gb/test/results";
         let url = Url::parse(url).unwrap();
-        let info = extract_url_info(&url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
         insta::assert_debug_snapshot!(info, @r#"
         Competition {
+            language: "en_gb",
             competition: "test",
         }
         "#);
@@ -201,9 +332,10 @@ mod tests {
         // Test without www prefix and with HTTP scheme
         let url = "http://soaringspot.com/en_gb/test/results";
         let url = Url::parse(url).unwrap();
-        let info = extract_url_info(&url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
         insta::assert_debug_snapshot!(info, @r#"
         Competition {
+            language: "en_gb",
             competition: "test",
         }
         "#);
@@ -211,69 +343,188 @@ mod tests {
         // Test error cases
         let url = "ftp://www.soaringspot.com/en_gb";
         let url = Url::parse(url).unwrap();
-        let result = extract_url_info(&url);
+        let result = extract_url_info(&url, &default_base_url());
         insta::assert_snapshot!(result.unwrap_err(), @"URL must use HTTP or HTTPS scheme");
 
         let url = "https://www.google.com";
         let url = Url::parse(url).unwrap();
-        let result = extract_url_info(&url);
-        insta::assert_snapshot!(result.unwrap_err(), @"URL must be from soaringspot.com or www.soaringspot.com");
+        let result = extract_url_info(&url, &default_base_url());
+        insta::assert_debug_snapshot!(result.as_ref().unwrap_err(), @r#"
+        UnsupportedHost {
+            host: "www.google.com",
+            base_url: "https://www.soaringspot.com/",
+        }
+        "#);
+        insta::assert_snapshot!(result.unwrap_err(), @"URL must be from the same host as https://www.soaringspot.com/, not www.google.com");
 
         let url = "https://www.soaringspot.com/en_gb";
         let url = Url::parse(url).unwrap();
-        let result = extract_url_info(&url);
+        let result = extract_url_info(&url, &default_base_url());
         insta::assert_snapshot!(result.unwrap_err(), @"URL must contain competition name");
 
         let url = "https://www.soaringspot.com/en_gb/test/invalid/club/task-1-on-2025-01-01/daily";
         let url = Url::parse(url).unwrap();
-        let result = extract_url_info(&url);
+        let result = extract_url_info(&url, &default_base_url());
         insta::assert_snapshot!(result.unwrap_err(), @"Unsupported URL format");
 
         let url = "https://www.soaringspot.com/en_gb/test/results/club/invalid-format";
         let url = Url::parse(url).unwrap();
-        let result = extract_url_info(&url);
+        let result = extract_url_info(&url, &default_base_url());
         insta::assert_snapshot!(result.unwrap_err(), @"Unsupported URL format");
 
         let url = "https://www.soaringspot.com/en_gb/test/results/club/task-1-on-invalid-date";
         let url = Url::parse(url).unwrap();
-        let result = extract_url_info(&url);
+        let result = extract_url_info(&url, &default_base_url());
         insta::assert_snapshot!(result.unwrap_err(), @"Failed to parse date 'invalid-date': strptime parsing failed: %Y failed: failed to parse year: invalid number, no digits found");
     }
 
+    #[test]
+    fn test_extract_url_info_language() {
+        let url = "https://www.soaringspot.com/cs/39th-fai-world-gliding-championships-tabor-2025/results/club/task-10-on-2025-06-19/daily";
+        let url = Url::parse(url).unwrap();
+        let info = extract_url_info(&url, &default_base_url()).unwrap();
+        insta::assert_debug_snapshot!(info, @r#"
+        Daily(
+            DailyUrlInfo {
+                language: "cs",
+                competition: "39th-fai-world-gliding-championships-tabor-2025",
+                class: "club",
+                date: 2025-06-19,
+                task_name: "task-10",
+            },
+        )
+        "#);
+
+        // Generated URLs keep the language
+        let UrlInfo::Daily(daily_info) = info else {
+            panic!("Expected Daily variant");
+        };
+        insta::assert_snapshot!(daily_info.to_daily_url(&default_base_url()), @"https://www.soaringspot.com/cs/39th-fai-world-gliding-championships-tabor-2025/results/club/task-10-on-2025-06-19/daily");
+
+        let url = Url::parse("https://www.soaringspot.com/pt_br/test/results/club").unwrap();
+        insta::assert_debug_snapshot!(extract_url_info(&url, &default_base_url()).unwrap(), @r#"
+        Class {
+            language: "pt_br",
+            competition: "test",
+            class: "club",
+        }
+        "#);
+
+        // URLs without a language prefix use the default language
+        let url = Url::parse("https://www.soaringspot.com/test/results").unwrap();
+        insta::assert_debug_snapshot!(extract_url_info(&url, &default_base_url()).unwrap(), @r#"
+        Competition {
+            language: "en_gb",
+            competition: "test",
+        }
+        "#);
+
+        // Competitions that look like a language are not taken for one
+        let url = Url::parse("https://www.soaringspot.com/gp/results/club").unwrap();
+        insta::assert_debug_snapshot!(extract_url_info(&url, &default_base_url()).unwrap(), @r#"
+        Class {
+            language: "en_gb",
+            competition: "gp",
+            class: "club",
+        }
+        "#);
+        let url = Url::parse("https://www.soaringspot.com/de/gp/results/club").unwrap();
+        insta::assert_debug_snapshot!(extract_url_info(&url, &default_base_url()).unwrap(), @r#"
+        Class {
+            language: "de",
+            competition: "gp",
+            class: "club",
+        }
+        "#);
+    }
+
+    #[test]
+    fn test_extract_url_info_base_url() {
+        let base_url = Url::parse("http://localhost:8080/mirror/").unwrap();
+
+        let url = "http://localhost:8080/mirror/en_gb/test/results/club/task-1-on-2025-01-01/daily";
+        let url = Url::parse(url).unwrap();
+        let UrlInfo::Daily(daily_info) = extract_url_info(&url, &base_url).unwrap() else {
+            panic!("Expected Daily variant");
+        };
+        assert_eq!(daily_info.to_daily_url(&base_url), url.as_str());
+        insta::assert_snapshot!(daily_info.to_task_url(&base_url), @"http://localhost:8080/mirror/en_gb/test/tasks/club/task-1-on-2025-01-01");
+        insta::assert_snapshot!(results_url(&base_url, "en_gb", "test"), @"http://localhost:8080/mirror/en_gb/test/results");
+
+        // SoaringSpot URLs are rejected when a different base URL is used
+        let url = Url::parse("https://www.soaringspot.com/en_gb/test/results").unwrap();
+        insta::assert_snapshot!(extract_url_info(&url, &base_url).unwrap_err(), @"URL must be from the same host as http://localhost:8080/mirror/, not www.soaringspot.com");
+
+        let url = Url::parse("http://localhost:8080/en_gb/test/results").unwrap();
+        insta::assert_snapshot!(extract_url_info(&url, &base_url).unwrap_err(), @"URL must start with the base URL http://localhost:8080/mirror/");
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let base_url = default_base_url();
+        insta::assert_snapshot!(resolve_url(&base_url, "/en_gb/test/results"), @"https://www.soaringspot.com/en_gb/test/results");
+        insta::assert_snapshot!(resolve_url(&base_url, "http://localhost/en_gb/test"), @"http://localhost/en_gb/test");
+
+        let base_url = Url::parse("http://localhost:8080/mirror").unwrap();
+        insta::assert_snapshot!(resolve_url(&base_url, "/en_gb/test/results"), @"http://localhost:8080/mirror/en_gb/test/results");
+
+        // Absolute links to SoaringSpot are moved to the mirror
+        let href = "https://www.soaringspot.com/en_gb/download-contest-flight/5039-1?dl=1";
+        insta::assert_snapshot!(resolve_url(&base_url, href), @"http://localhost:8080/mirror/en_gb/download-contest-flight/5039-1?dl=1");
+        insta::assert_snapshot!(resolve_url(&base_url, "http://soaringspot.com/en_gb/test"), @"http://localhost:8080/mirror/en_gb/test");
+        insta::assert_snapshot!(resolve_url(&base_url, "https://example.com/en_gb/test"), @"https://example.com/en_gb/test");
+    }
+
     #[test]
     fn test_daily_url_info_to_daily_url() {
         // Test URL generation
         let daily_info = DailyUrlInfo {
+            language: "en_gb".to_string(),
             competition: "39th-fai-world-gliding-championships-tabor-2025".to_string(),
             class: "club".to_string(),
             date: Date::constant(2025, 6, 19),
             task_name: "task-10".to_string(),
         };
 
-        let url = daily_info.to_daily_url();
+        let url = daily_info.to_daily_url(&default_base_url());
         insta::assert_snapshot!(url, @"https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/task-10-on-2025-06-19/daily");
 
         // Test with different values
         let daily_info = DailyUrlInfo {
+            language: "en_gb".to_string(),
             competition: "test-competition".to_string(),
             class: "standard".to_string(),
             date: Date::constant(2024, 12, 1),
             task_name: "task-5".to_string(),
         };
 
-        let url = daily_info.to_daily_url();
+        let url = daily_info.to_daily_url(&default_base_url());
         insta::assert_snapshot!(url, @"https://www.soaringspot.com/en_gb/test-competition/results/standard/task-5-on-2024-12-01/daily");
     }
 
+    #[test]
+    fn test_daily_url_info_to_task_url() {
+        let daily_info = DailyUrlInfo {
+            language: "en_gb".to_string(),
+            competition: "39th-fai-world-gliding-championships-tabor-2025".to_string(),
+            class: "club".to_string(),
+            date: Date::constant(2025, 6, 19),
+            task_name: "task-10".to_string(),
+        };
+
+        let url = daily_info.to_task_url(&default_base_url());
+        insta::assert_snapshot!(url, @"https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/tasks/club/task-10-on-2025-06-19");
+    }
+
     #[test]
     fn test_url_roundtrip() {
         // Test that we can parse a URL and generate the same URL back
         let original_url = "https://www.soaringspot.com/en_gb/39th-fai-world-gliding-championships-tabor-2025/results/club/task-10-on-2025-06-19/daily";
         let parsed_url = Url::parse(original_url).unwrap();
-        let url_info = extract_url_info(&parsed_url).unwrap();
+        let url_info = extract_url_info(&parsed_url, &default_base_url()).unwrap();
 
         if let UrlInfo::Daily(daily_info) = url_info {
-            let generated_url = daily_info.to_daily_url();
+            let generated_url = daily_info.to_daily_url(&default_base_url());
             assert_eq!(generated_url, original_url);
         } else {
             panic!("Expected Daily variant");
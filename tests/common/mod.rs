@@ -0,0 +1,201 @@
+//! A local stand-in for SoaringSpot that serves canned responses, so that
+//! the full download flow can be tested without hitting the real site.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::process::{Output, Stdio};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, Command};
+
+/// A minimal IGC file that passes the validation of the downloader.
+pub const IGC_BODY: &str = "AXXX001\r\nHFDTE190625\r\nB1200004900000N01400000EA0050000500\r\n";
+
+/// A canned HTTP response.
+#[derive(Debug, Clone)]
+pub struct Response {
+    status: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: String,
+}
+
+impl Response {
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self::status("200 OK").body(body)
+    }
+
+    pub fn status(status: &'static str) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: String::new(),
+        }
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Responses per path, the last one is repeated for further requests
+    routes: HashMap<String, VecDeque<Response>>,
+    /// Paths of all requests, in the order they were received
+    requests: Vec<String>,
+}
+
+/// HTTP server on a random local port. Paths without a route are answered
+/// with "404 Not Found".
+#[derive(Debug, Clone)]
+pub struct MockServer {
+    base_url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Self {
+            base_url: format!("http://{addr}"),
+            state: Arc::default(),
+        };
+
+        let state = server.state.clone();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(handle_connection(socket, state.clone()));
+            }
+        });
+
+        server
+    }
+
+    /// Returns the base URL of the server, without a trailing slash.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Adds a response for `path`. Several responses for the same path are
+    /// served in order.
+    pub fn route(&self, path: &str, response: Response) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .routes
+            .entry(path.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Returns the number of requests received for `path`.
+    pub fn request_count(&self, path: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state
+            .requests
+            .iter()
+            .filter(|request| *request == path)
+            .count()
+    }
+
+    /// Returns the number of requests received for paths containing
+    /// `pattern`.
+    pub fn request_count_matching(&self, pattern: &str) -> usize {
+        let state = self.state.lock().unwrap();
+        state
+            .requests
+            .iter()
+            .filter(|request| request.contains(pattern))
+            .count()
+    }
+
+    /// Runs the downloader binary against this server.
+    pub async fn run(&self, path: &str, output: &Path, args: &[&str]) -> Output {
+        self.command(path, output, args).output().await.unwrap()
+    }
+
+    /// Starts the downloader binary against this server without waiting for
+    /// it to exit, e.g. in watch mode. Its stdout is piped, and it is killed
+    /// when the child is dropped.
+    pub fn spawn(&self, path: &str, output: &Path, args: &[&str]) -> Child {
+        self.command(path, output, args)
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap()
+    }
+
+    fn command(&self, path: &str, output: &Path, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_soaringspot-igc-downloader"));
+        command
+            .arg(format!("{}{path}", self.base_url))
+            .arg("--base-url")
+            .arg(&self.base_url)
+            .arg("--output")
+            .arg(output)
+            .args(["--requests-per-second", "0", "--retry-delay-ms", "10"])
+            .args(args);
+        command
+    }
+
+    /// Runs a subcommand like `classes` of the binary against this server.
+    pub async fn run_command(&self, command: &str, path: &str, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_soaringspot-igc-downloader"))
+            .arg(command)
+            .arg(format!("{}{path}", self.base_url))
+            .arg("--base-url")
+            .arg(&self.base_url)
+            .args(["--requests-per-second", "0", "--retry-delay-ms", "10"])
+            .args(args)
+            .output()
+            .await
+            .unwrap()
+    }
+}
+
+async fn handle_connection(mut socket: TcpStream, state: Arc<Mutex<State>>) {
+    // Only GET requests are expected, so the request ends with the headers
+    let mut request = vec![];
+    let mut buffer = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        match socket.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => request.extend_from_slice(&buffer[..read]),
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let target = request.split_whitespace().nth(1).unwrap_or_default();
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(path.clone());
+        match state.routes.get_mut(&path) {
+            Some(responses) if responses.len() > 1 => responses.pop_front().unwrap(),
+            Some(responses) => responses[0].clone(),
+            None => Response::status("404 Not Found"),
+        }
+    };
+
+    let mut head = format!(
+        "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+
+    let _ = socket.write_all(head.as_bytes()).await;
+    let _ = socket.write_all(response.body.as_bytes()).await;
+}
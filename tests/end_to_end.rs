@@ -0,0 +1,881 @@
+//! Runs the downloader binary, and the library it wraps, against a local
+//! mock of SoaringSpot.
+
+mod common;
+
+use common::{IGC_BODY, MockServer, Response};
+use jiff::civil::Date;
+use soaringspot_igc_downloader::export::{ExportFormat, read_results};
+use soaringspot_igc_downloader::manifest::{MANIFEST_FILENAME, Manifest};
+use soaringspot_igc_downloader::{
+    DownloadOptions, Downloader, Event, IgcFile, Scheduler, date_to_igc_filename_prefix,
+    parse_igc_files,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use url::Url;
+
+const COMPETITION: &str = "test-competition";
+const DAY_HTML: &str = include_str!("fixtures/day.html");
+const DAYS: [(&str, Date); 2] = [
+    ("task-9", Date::constant(2025, 6, 18)),
+    ("task-10", Date::constant(2025, 6, 19)),
+];
+
+fn results_path() -> String {
+    format!("/en_gb/{COMPETITION}/results")
+}
+
+fn daily_path(task_name: &str, date: Date) -> String {
+    format!("/en_gb/{COMPETITION}/results/club/{task_name}-on-{date}/daily")
+}
+
+/// Returns the path of the download URL of an IGC file.
+fn download_path(igc_file: &IgcFile) -> String {
+    Url::parse(&igc_file.download_url)
+        .unwrap()
+        .path()
+        .to_string()
+}
+
+/// Returns the location of the IGC file of `callsign` on `date`.
+fn igc_path(output: &Path, date: Date, callsign: &str) -> PathBuf {
+    let filename = format!("{}_{callsign}.igc", date_to_igc_filename_prefix(date));
+    output
+        .join(COMPETITION)
+        .join("club")
+        .join(date.to_string())
+        .join(filename)
+}
+
+/// Sets up a competition with one class and two days, which both use the
+/// daily results fixture. Routes for the pages and files are only added
+/// if `routes` is set, so that tests can add their own responses first.
+///
+/// Returns the IGC files listed on each day.
+fn competition(server: &MockServer, routes: bool) -> Vec<IgcFile> {
+    let links = DAYS
+        .iter()
+        .map(|(task_name, date)| format!("<a href=\"{}\">Day</a>", daily_path(task_name, *date)))
+        .collect::<String>();
+    server.route(
+        &results_path(),
+        Response::ok(format!("<html><body>{links}</body></html>")),
+    );
+
+    let base_url = Url::parse(server.base_url()).unwrap();
+    let igc_files = parse_igc_files(DAY_HTML, &base_url).unwrap();
+    assert!(!igc_files.is_empty());
+
+    if routes {
+        for (task_name, date) in DAYS {
+            server.route(&daily_path(task_name, date), Response::ok(DAY_HTML));
+        }
+        for igc_file in &igc_files {
+            server.route(&download_path(igc_file), Response::ok(IGC_BODY));
+        }
+    }
+
+    igc_files
+}
+
+fn stdout(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[tokio::test]
+async fn test_downloads_competition_into_directory_layout() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, true);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = server.run(&results_path(), output.path(), &[]).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    assert!(stdout(&result).contains(&format!("Found {} IGC files", 2 * igc_files.len())));
+
+    for (_, date) in DAYS {
+        for igc_file in &igc_files {
+            let path = igc_path(output.path(), date, &igc_file.callsign);
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), IGC_BODY);
+        }
+    }
+
+    let manifest_path = output.path().join(COMPETITION).join(MANIFEST_FILENAME);
+    let manifest = Manifest::load(&manifest_path).await.unwrap().unwrap();
+    assert_eq!(manifest.competition, COMPETITION);
+    assert_eq!(manifest.days.len(), DAYS.len());
+    assert_eq!(manifest.files().len(), DAYS.len() * igc_files.len());
+}
+
+#[tokio::test]
+async fn test_absolute_links_use_base_url() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, false);
+    let html = include_str!("fixtures/results_absolute.html");
+    server.route(&results_path(), Response::ok(html));
+    for (task_name, date) in DAYS {
+        server.route(&daily_path(task_name, date), Response::ok(DAY_HTML));
+    }
+    for igc_file in &igc_files {
+        server.route(&download_path(igc_file), Response::ok(IGC_BODY));
+    }
+    let output = tempfile::tempdir().unwrap();
+
+    let result = server.run(&results_path(), output.path(), &[]).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    for (task_name, date) in DAYS {
+        assert_eq!(server.request_count(&daily_path(task_name, date)), 1);
+        let path = igc_path(output.path(), date, &igc_files[0].callsign);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), IGC_BODY);
+    }
+}
+
+#[tokio::test]
+async fn test_skips_existing_files() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, true);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = server.run(&results_path(), output.path(), &[]).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let downloads = server.request_count_matching("download-contest-flight");
+
+    // Local changes are kept, because existing files are not downloaded again
+    let path = igc_path(output.path(), DAYS[0].1, &igc_files[0].callsign);
+    std::fs::write(&path, "local copy").unwrap();
+
+    let result = server.run(&results_path(), output.path(), &[]).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    assert_eq!(
+        server.request_count_matching("download-contest-flight"),
+        downloads
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "local copy");
+}
+
+#[tokio::test]
+async fn test_library_reports_events() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, true);
+    let output = tempfile::tempdir().unwrap();
+
+    let base_url = Url::parse(server.base_url()).unwrap();
+    let scheduler = Scheduler::new(reqwest::Client::new())
+        .with_requests_per_second(0.0)
+        .with_base_url(base_url.clone());
+    let options = DownloadOptions {
+        output_dir: output.path().to_path_buf(),
+        ..DownloadOptions::default()
+    };
+    let events = Mutex::new(vec![]);
+    let downloader = Downloader::new(&scheduler, options).with_events(|event| {
+        let event = match event {
+            Event::LoadingDays(len) => format!("loading {len} days"),
+            Event::FilesFound(len) => format!("found {len} files"),
+            Event::FileDone { status, .. } => format!("{status:?}"),
+            Event::ManifestWritten(_) => "manifest".to_string(),
+            _ => return,
+        };
+        events.lock().unwrap().push(event);
+    });
+
+    let url = base_url.join(&results_path()).unwrap();
+    let summary = downloader.run(&url).await.unwrap();
+    assert_eq!(summary.new_days, DAYS.len());
+    assert_eq!(summary.downloaded_files, DAYS.len() * igc_files.len());
+
+    let files = DAYS.len() * igc_files.len();
+    let mut expected = vec![
+        format!("loading {} days", DAYS.len()),
+        format!("found {files} files"),
+    ];
+    expected.extend(vec!["Downloaded".to_string(); files]);
+    expected.push("manifest".to_string());
+    assert_eq!(*events.lock().unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_library_dry_run_returns_plan() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, true);
+    let output = tempfile::tempdir().unwrap();
+
+    let base_url = Url::parse(server.base_url()).unwrap();
+    let scheduler = Scheduler::new(reqwest::Client::new())
+        .with_requests_per_second(0.0)
+        .with_base_url(base_url.clone());
+    let options = DownloadOptions {
+        output_dir: output.path().to_path_buf(),
+        dry_run: true,
+        ..DownloadOptions::default()
+    };
+
+    let url = base_url.join(&results_path()).unwrap();
+    let summary = Downloader::new(&scheduler, options)
+        .run(&url)
+        .await
+        .unwrap();
+    assert_eq!(summary.plan.len(), DAYS.len() * igc_files.len());
+    let path = igc_path(output.path(), DAYS[0].1, &igc_files[0].callsign);
+    assert_eq!(summary.plan[0].path, path.to_str().unwrap());
+    assert!(!summary.plan[0].exists);
+    assert_eq!(server.request_count_matching("download-contest-flight"), 0);
+    assert_eq!(std::fs::read_dir(output.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_sync_only_loads_changes() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, false);
+
+    // The first day is unchanged on the second run, on the second day the
+    // first flight was replaced by a file with another URL
+    let (unchanged_task_name, unchanged_date) = DAYS[0];
+    let unchanged_day = daily_path(unchanged_task_name, unchanged_date);
+    server.route(
+        &unchanged_day,
+        Response::ok(DAY_HTML).header("etag", "\"day-9\""),
+    );
+    server.route(&unchanged_day, Response::status("304 Not Modified"));
+    let (task_name, date) = DAYS[1];
+    let replaced = download_path(&igc_files[0]);
+    let replaced_id = replaced.rsplit('/').next().unwrap();
+    let html = DAY_HTML.replace(replaced_id, "5039-1");
+    server.route(&daily_path(task_name, date), Response::ok(DAY_HTML));
+    server.route(&daily_path(task_name, date), Response::ok(html));
+    server.route(&replaced, Response::ok(IGC_BODY));
+    let replacement = "/en_gb/download-contest-flight/5039-1";
+    let body = format!("{IGC_BODY}LPLTreplaced\r\n");
+    server.route(replacement, Response::ok(body.clone()));
+
+    // Files with validators are requested conditionally, the others are
+    // only requested again if their URL changes
+    let validated = download_path(&igc_files[1]);
+    for _ in DAYS {
+        server.route(
+            &validated,
+            Response::ok(IGC_BODY).header("etag", "\"flight\""),
+        );
+    }
+    server.route(&validated, Response::status("304 Not Modified"));
+    for igc_file in &igc_files[2..] {
+        server.route(&download_path(igc_file), Response::ok(IGC_BODY));
+    }
+    competition(&server, false);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = server
+        .run(&results_path(), output.path(), &["--sync"])
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let downloads = server.request_count_matching("download-contest-flight");
+    assert_eq!(downloads, DAYS.len() * igc_files.len());
+
+    let result = server
+        .run(&results_path(), output.path(), &["--sync"])
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    assert_eq!(server.request_count(&unchanged_day), 2);
+    assert_eq!(server.request_count(replacement), 1);
+    assert_eq!(server.request_count(&validated), DAYS.len() + 1);
+    assert_eq!(
+        server.request_count_matching("download-contest-flight"),
+        downloads + 2
+    );
+
+    // The replaced flight is swapped in without leaving partial files
+    let path = igc_path(output.path(), date, &igc_files[0].callsign);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), body);
+    let unchanged_path = igc_path(output.path(), unchanged_date, &igc_files[0].callsign);
+    assert_eq!(std::fs::read_to_string(&unchanged_path).unwrap(), IGC_BODY);
+    let day_dir = path.parent().unwrap();
+    assert_eq!(std::fs::read_dir(day_dir).unwrap().count(), igc_files.len());
+
+    let manifest_path = output.path().join(COMPETITION).join(MANIFEST_FILENAME);
+    let manifest = Manifest::load(&manifest_path).await.unwrap().unwrap();
+    let relative_path = path.strip_prefix(output.path().join(COMPETITION)).unwrap();
+    let entry = manifest.get(relative_path).unwrap();
+    assert!(entry.source_url.ends_with("/5039-1?dl=1"), "{entry:?}");
+}
+
+#[tokio::test]
+async fn test_watch() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, false);
+    for (task_name, date) in DAYS {
+        let path = daily_path(task_name, date);
+        server.route(&path, Response::ok(DAY_HTML).header("etag", "\"day\""));
+        server.route(&path, Response::status("304 Not Modified"));
+    }
+    for igc_file in &igc_files {
+        server.route(&download_path(igc_file), Response::ok(IGC_BODY));
+    }
+    let output = tempfile::tempdir().unwrap();
+
+    let args = ["--watch", "--interval", "1s"];
+    let mut child = server.spawn(&results_path(), output.path(), &args);
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut cycles = vec![];
+    let read_cycles = async {
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if line.starts_with("Cycle ") {
+                cycles.push(line);
+                if cycles.len() == 2 {
+                    break;
+                }
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(30), read_cycles)
+        .await
+        .unwrap();
+    child.kill().await.unwrap();
+
+    // The second cycle finds both days unchanged and downloads nothing
+    let flights = DAYS.len() * igc_files.len();
+    assert_eq!(
+        cycles,
+        [
+            format!(
+                "Cycle 1: 2 new days, 0 changed days, 0 unchanged days, 0 failed days, \
+                 {flights} new flights, 0 updated flights, 0 failed flights"
+            ),
+            "Cycle 2: 0 new days, 0 changed days, 2 unchanged days, 0 failed days, \
+             0 new flights, 0 updated flights, 0 failed flights"
+                .to_string(),
+        ]
+    );
+    assert_eq!(
+        server.request_count_matching("download-contest-flight"),
+        flights
+    );
+}
+
+#[tokio::test]
+async fn test_retries_rate_limited_requests() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, false);
+
+    // Rate limiting with a status code, and with a page served as success
+    let (task_name, date) = DAYS[1];
+    server.route(
+        &daily_path(task_name, date),
+        Response::status("429 Too Many Requests").header("retry-after", "0"),
+    );
+    let rate_limited = download_path(&igc_files[0]);
+    server.route(
+        &rate_limited,
+        Response::ok("<html><h1>Too Many Requests</h1></html>"),
+    );
+    // Server errors are retried as well
+    let (failed_task_name, failed_date) = DAYS[0];
+    server.route(
+        &daily_path(failed_task_name, failed_date),
+        Response::status("503 Service Unavailable"),
+    );
+    competition(&server, true);
+
+    let output = tempfile::tempdir().unwrap();
+    let result = server.run(&results_path(), output.path(), &[]).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+
+    assert_eq!(server.request_count(&daily_path(task_name, date)), 2);
+    assert_eq!(
+        server.request_count(&daily_path(failed_task_name, failed_date)),
+        2
+    );
+    // Once rate limited, then once for each day
+    assert_eq!(server.request_count(&rate_limited), 3);
+    for (_, date) in DAYS {
+        let path = igc_path(output.path(), date, &igc_files[0].callsign);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), IGC_BODY);
+    }
+}
+
+#[tokio::test]
+async fn test_reports_http_errors() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, false);
+
+    let (failed_task_name, failed_date) = DAYS[0];
+    server.route(
+        &daily_path(failed_task_name, failed_date),
+        Response::status("404 Not Found"),
+    );
+    let (task_name, date) = DAYS[1];
+    server.route(&daily_path(task_name, date), Response::ok(DAY_HTML));
+    let (failed_file, igc_files) = igc_files.split_first().unwrap();
+    server.route(
+        &download_path(failed_file),
+        Response::status("500 Internal Server Error"),
+    );
+    for igc_file in igc_files {
+        server.route(&download_path(igc_file), Response::ok(IGC_BODY));
+    }
+
+    let output = tempfile::tempdir().unwrap();
+    let result = server
+        .run(&results_path(), output.path(), &["--max-retries", "1"])
+        .await;
+    assert!(!result.status.success());
+    let stdout = stdout(&result);
+    assert!(
+        stdout.contains("Failed to load 1 daily results pages"),
+        "{stdout}"
+    );
+    assert!(stdout.contains(&daily_path(failed_task_name, failed_date)));
+
+    // The other files of the day that could be loaded are still downloaded
+    for igc_file in igc_files {
+        assert!(igc_path(output.path(), date, &igc_file.callsign).exists());
+    }
+    let class_dir = output.path().join(COMPETITION).join("club");
+    assert!(!class_dir.join(failed_date.to_string()).exists());
+    let failed_path = igc_path(output.path(), date, &failed_file.callsign);
+    assert!(!failed_path.exists());
+    // The server error is retried once before giving up
+    assert_eq!(server.request_count(&download_path(failed_file)), 2);
+    assert_eq!(
+        std::fs::read_dir(failed_path.parent().unwrap())
+            .unwrap()
+            .count(),
+        igc_files.len()
+    );
+
+    // Neither day is recorded, so that both are loaded again on the next sync
+    let manifest_path = output.path().join(COMPETITION).join(MANIFEST_FILENAME);
+    let manifest = Manifest::load(&manifest_path).await.unwrap().unwrap();
+    assert!(manifest.days.is_empty());
+    assert_eq!(manifest.files().len(), igc_files.len());
+}
+
+#[tokio::test]
+async fn test_filters_downloads() {
+    let server = MockServer::start().await;
+    competition(&server, true);
+    let output = tempfile::tempdir().unwrap();
+
+    let args = ["--callsign", "AM,A3", "--glider", "ls 3"];
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+
+    for (_, date) in DAYS {
+        let dir = output
+            .path()
+            .join(COMPETITION)
+            .join("club")
+            .join(date.to_string());
+        let mut filenames = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        filenames.sort();
+        let prefix = date_to_igc_filename_prefix(date);
+        assert_eq!(filenames, [format!("{prefix}_AM.igc")]);
+    }
+}
+
+#[tokio::test]
+async fn test_custom_layout() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, true);
+    let output = tempfile::tempdir().unwrap();
+
+    let args = [
+        "--layout",
+        "flights/{date}_{callsign}.igc",
+        "--export",
+        "csv",
+    ];
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+
+    let flights = output.path().join("flights");
+    assert_eq!(
+        std::fs::read_dir(&flights).unwrap().count(),
+        DAYS.len() * igc_files.len()
+    );
+    let path = flights.join(format!("{}_{}.igc", DAYS[0].1, igc_files[0].callsign));
+    assert_eq!(std::fs::read_to_string(path).unwrap(), IGC_BODY);
+
+    // The results files point to the IGC files relative to themselves
+    let competition_dir = output.path().join(COMPETITION);
+    let day_dir = competition_dir.join("club").join(DAYS[0].1.to_string());
+    for dir in [day_dir, competition_dir] {
+        let records = read_results(&dir.join("results.csv"), ExportFormat::Csv)
+            .await
+            .unwrap();
+        let igc_files = records
+            .iter()
+            .filter_map(|record| record.igc_file.as_ref())
+            .collect::<Vec<_>>();
+        assert!(!igc_files.is_empty());
+        for igc_file in igc_files {
+            assert!(igc_file.starts_with("../"), "{igc_file}");
+            let path = dir.join(igc_file);
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), IGC_BODY);
+        }
+    }
+
+    // Paths in the manifest are relative to the competition directory
+    let manifest_path = output.path().join(COMPETITION).join(MANIFEST_FILENAME);
+    let manifest = Manifest::load(&manifest_path).await.unwrap().unwrap();
+    let relative_path =
+        Path::new("../flights").join(format!("{}_{}.igc", DAYS[0].1, igc_files[0].callsign));
+    assert!(manifest.get(&relative_path).is_some());
+}
+
+#[tokio::test]
+async fn test_rejects_invalid_layout() {
+    let server = MockServer::start().await;
+    let output = tempfile::tempdir().unwrap();
+
+    let args = ["--layout", "{date}/{glider}.igc"];
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(
+        stderr.contains("unknown placeholder '{glider}'"),
+        "{stderr}"
+    );
+    assert_eq!(server.request_count(&results_path()), 0);
+}
+
+#[tokio::test]
+async fn test_long_filenames() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, false);
+    for (task_name, date) in DAYS {
+        server.route(&daily_path(task_name, date), Response::ok(DAY_HTML));
+    }
+    // All other flights are from the same logger
+    let body = IGC_BODY.replace("AXXX001", "ALXVABC");
+    server.route(&download_path(&igc_files[0]), Response::ok(body));
+    for igc_file in &igc_files[1..] {
+        server.route(&download_path(igc_file), Response::ok(IGC_BODY));
+    }
+    let output = tempfile::tempdir().unwrap();
+
+    let args = ["--long-filenames"];
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+
+    let (_, date) = DAYS[0];
+    let day_dir = output
+        .path()
+        .join(COMPETITION)
+        .join("club")
+        .join(date.to_string());
+    let mut filenames = std::fs::read_dir(&day_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    filenames.sort();
+    let mut expected = (1..igc_files.len())
+        .map(|number| format!("{date}-XXX-001-{number:02}.igc"))
+        .collect::<Vec<_>>();
+    expected.push(format!("{date}-LXV-ABC-01.igc"));
+    expected.sort();
+    assert_eq!(filenames, expected);
+
+    // Files from the previous run are found by their flight
+    let downloads = server.request_count_matching("download-contest-flight");
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    assert_eq!(
+        server.request_count_matching("download-contest-flight"),
+        downloads
+    );
+    assert_eq!(
+        std::fs::read_dir(&day_dir).unwrap().count(),
+        igc_files.len()
+    );
+}
+
+#[tokio::test]
+async fn test_unsafe_and_colliding_callsigns() {
+    let server = MockServer::start().await;
+    competition(&server, false);
+    let html = DAY_HTML
+        .replace("\n                                    A3\n", "\n am \n")
+        .replace("\n                                    CP\n", "\n A/M \n")
+        .replace("\n                                    FL\n", "\n A M \n");
+    for (task_name, date) in DAYS {
+        server.route(&daily_path(task_name, date), Response::ok(html.clone()));
+    }
+    let base_url = Url::parse(server.base_url()).unwrap();
+    for igc_file in parse_igc_files(&html, &base_url).unwrap() {
+        let body = format!("{IGC_BODY}LPLT{}\r\n", igc_file.callsign);
+        server.route(&download_path(&igc_file), Response::ok(body));
+    }
+    let output = tempfile::tempdir().unwrap();
+
+    let args = ["--callsign", "AM,A/M,A M"];
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let downloads = server.request_count_matching("download-contest-flight");
+
+    // Flights that would share a file are numbered in the order of their
+    // callsigns, which is the same on every run
+    let (_, date) = DAYS[0];
+    let prefix = date_to_igc_filename_prefix(date);
+    let day_dir = output
+        .path()
+        .join(COMPETITION)
+        .join("club")
+        .join(date.to_string());
+    let expected = [
+        ("A M", format!("{prefix}_A_M.igc")),
+        ("A/M", format!("{prefix}_A_M_2.igc")),
+        ("AM", format!("{prefix}_AM.igc")),
+        ("am", format!("{prefix}_am_2.igc")),
+    ];
+    let check_files = || {
+        let mut filenames = std::fs::read_dir(&day_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        filenames.sort();
+        let mut expected_filenames = expected.clone().map(|(_, filename)| filename);
+        expected_filenames.sort();
+        assert_eq!(filenames, expected_filenames);
+
+        for (callsign, filename) in &expected {
+            let content = std::fs::read_to_string(day_dir.join(filename)).unwrap();
+            assert!(
+                content.ends_with(&format!("LPLT{callsign}\r\n")),
+                "{filename}"
+            );
+        }
+    };
+    check_files();
+
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    assert_eq!(
+        server.request_count_matching("download-contest-flight"),
+        downloads
+    );
+    check_files();
+}
+
+#[tokio::test]
+async fn test_callsigns_colliding_across_runs() {
+    let server = MockServer::start().await;
+    competition(&server, false);
+
+    // The flight of "am" is downloaded first, "AM" only shows up later
+    let first_html = DAY_HTML
+        .replace("\n                                    A3\n", "\n am \n")
+        .replace("\n                                    AM\n", "\n QQ \n");
+    let html = DAY_HTML.replace("\n                                    A3\n", "\n am \n");
+    for (task_name, date) in DAYS {
+        server.route(&daily_path(task_name, date), Response::ok(&first_html));
+        server.route(&daily_path(task_name, date), Response::ok(&html));
+    }
+    let base_url = Url::parse(server.base_url()).unwrap();
+    let igc_files = parse_igc_files(&html, &base_url).unwrap();
+    for igc_file in &igc_files {
+        let body = format!("{IGC_BODY}LPLT{}\r\n", igc_file.callsign);
+        server.route(&download_path(igc_file), Response::ok(body));
+    }
+    let output = tempfile::tempdir().unwrap();
+
+    let args = ["--callsign", "am"];
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+
+    // "am" keeps its file, which is the same as the one of "AM" on
+    // case-insensitive filesystems, so "AM" gets a numbered one
+    let (_, date) = DAYS[0];
+    let prefix = date_to_igc_filename_prefix(date);
+    let day_dir = output
+        .path()
+        .join(COMPETITION)
+        .join("club")
+        .join(date.to_string());
+    let mut filenames = std::fs::read_dir(&day_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    filenames.sort();
+    assert_eq!(
+        filenames,
+        [format!("{prefix}_AM_2.igc"), format!("{prefix}_am.igc")]
+    );
+    for (callsign, filename) in [("AM", "AM_2"), ("am", "am")] {
+        let path = day_dir.join(format!("{prefix}_{filename}.igc"));
+        let content = std::fs::read_to_string(path).unwrap();
+        assert!(
+            content.ends_with(&format!("LPLT{callsign}\r\n")),
+            "{content}"
+        );
+    }
+
+    // Each flight was only downloaded once per day
+    for igc_file in igc_files.iter().filter(|file| file.callsign.contains('m')) {
+        assert_eq!(server.request_count(&download_path(igc_file)), DAYS.len());
+    }
+}
+
+#[tokio::test]
+async fn test_combined_export_covers_earlier_runs() {
+    let server = MockServer::start().await;
+    competition(&server, true);
+    let output = tempfile::tempdir().unwrap();
+
+    let args = ["--export", "csv"];
+    let result = server.run(&results_path(), output.path(), &args).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let combined_path = output.path().join(COMPETITION).join("results.csv");
+    let combined = std::fs::read_to_string(&combined_path).unwrap();
+
+    // Exporting a single day again keeps the other day in the combined file
+    let (task_name, date) = DAYS[1];
+    let result = server
+        .run(&daily_path(task_name, date), output.path(), &args)
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    assert_eq!(std::fs::read_to_string(&combined_path).unwrap(), combined);
+    for (_, date) in DAYS {
+        assert!(combined.contains(&format!(",club,{date},")), "{combined}");
+    }
+
+    // A filter only limits the downloads, the exports keep all pilots
+    let filtered_args = ["--export", "csv", "--callsign", "AM"];
+    let result = server
+        .run(&results_path(), output.path(), &filtered_args)
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    assert_eq!(std::fs::read_to_string(&combined_path).unwrap(), combined);
+
+    // Days that can't be read anymore are left out with a warning
+    let (_, stale_date) = DAYS[0];
+    let stale_path = output
+        .path()
+        .join(COMPETITION)
+        .join("club")
+        .join(stale_date.to_string())
+        .join("results.csv");
+    std::fs::write(&stale_path, "callsign,score\nAM,1000\n").unwrap();
+    let result = server
+        .run(&daily_path(task_name, date), output.path(), &args)
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    assert!(
+        stdout(&result).contains("⚠ Skipping invalid"),
+        "{}",
+        stdout(&result)
+    );
+    let combined = std::fs::read_to_string(&combined_path).unwrap();
+    assert!(
+        !combined.contains(&format!(",club,{stale_date},")),
+        "{combined}"
+    );
+    assert!(combined.contains(&format!(",club,{date},")), "{combined}");
+}
+
+#[tokio::test]
+async fn test_dry_run() {
+    let server = MockServer::start().await;
+    let igc_files = competition(&server, true);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = server
+        .run(&results_path(), output.path(), &["--dry-run", "--tasks"])
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let table = stdout(&result);
+    assert!(table.contains("CALLSIGN"), "{table}");
+    let totals = format!(
+        "{} IGC files on {} days in 1 classes, 0 already exist",
+        DAYS.len() * igc_files.len(),
+        DAYS.len()
+    );
+    assert!(table.contains(&totals), "{table}");
+
+    // Nothing is downloaded or written
+    assert_eq!(server.request_count_matching("download-contest-flight"), 0);
+    assert_eq!(server.request_count_matching("/tasks/"), 0);
+    assert_eq!(std::fs::read_dir(output.path()).unwrap().count(), 0);
+
+    // Existing files are marked, and stdout only contains the JSON plan
+    let (_, date) = DAYS[0];
+    let path = igc_path(output.path(), date, &igc_files[0].callsign);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, IGC_BODY).unwrap();
+
+    let result = server
+        .run(&results_path(), output.path(), &["--dry-run=json"])
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let plan: Vec<serde_json::Value> = serde_json::from_slice(&result.stdout).unwrap();
+    assert_eq!(plan.len(), DAYS.len() * igc_files.len());
+    let existing = plan
+        .iter()
+        .filter(|file| file["exists"] == true)
+        .map(|file| file["path"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(existing, [path.to_str().unwrap()]);
+    assert_eq!(plan[0]["pilot"], "John Smith");
+    assert_eq!(server.request_count_matching("download-contest-flight"), 0);
+}
+
+#[tokio::test]
+async fn test_inspection_commands() {
+    let server = MockServer::start().await;
+    for (task_name, date) in DAYS {
+        server.route(&daily_path(task_name, date), Response::ok(DAY_HTML));
+    }
+
+    // Add a practice day, which is only loaded when listing the days
+    let practice_path = daily_path("practice-1", Date::constant(2025, 6, 16));
+    let links = DAYS
+        .iter()
+        .map(|(task_name, date)| daily_path(task_name, *date))
+        .chain([practice_path.clone()])
+        .map(|path| format!("<a href=\"{path}\">Day</a>"))
+        .collect::<String>();
+    server.route(
+        &results_path(),
+        Response::ok(format!("<html><body>{links}</body></html>")),
+    );
+
+    let result = server
+        .run_command("classes", &results_path(), &["--format", "json"])
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let classes: serde_json::Value = serde_json::from_slice(&result.stdout).unwrap();
+    assert_eq!(classes[0]["class"], "club");
+    assert_eq!(classes[0]["days"], DAYS.len());
+    assert_eq!(classes[0]["practice_days"], 1);
+
+    let result = server.run_command("days", &results_path(), &[]).await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let table = stdout(&result);
+    let practice_day = table
+        .lines()
+        .find(|line| line.contains("practice-1"))
+        .unwrap();
+    assert!(practice_day.contains("yes"), "{table}");
+
+    // Practice days are left out of the pilots, and nothing is downloaded
+    let result = server
+        .run_command("pilots", &results_path(), &["--format", "json"])
+        .await;
+    assert!(result.status.success(), "{}", stdout(&result));
+    let pilots: Vec<serde_json::Value> = serde_json::from_slice(&result.stdout).unwrap();
+    assert_eq!(pilots.len(), 42);
+    let am = pilots
+        .iter()
+        .find(|pilot| pilot["callsign"] == "AM")
+        .unwrap();
+    assert_eq!(am["pilot"], "John Smith");
+    assert_eq!(am["flights"], DAYS.len());
+    assert_eq!(server.request_count(&practice_path), 0);
+    assert_eq!(server.request_count_matching("download-contest-flight"), 0);
+}